@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+
+use crate::cli::{Rendition, NAME_FILE_IN, NAME_FILE_OUT};
+use crate::error::{Error, Result};
+use crate::stdio::{is_stdio, STDIO};
+use crate::template::{is_template, OutputTemplate};
+
+/// Default output path, if only a single input file is supplied
+const DEFAULT_FILE_OUT: &str = "thumbnail.png";
+
+/// Representation of a single image to process as a struct
+pub struct Task {
+    /// Contains the path of the image to load
+    pub input: PathBuf,
+    /// Contains the path of the image to store, which may be an output template
+    pub output: PathBuf,
+    /// Contains the position of the image in the batch, starting at 0
+    pub index: usize,
+}
+
+/// This function resolves the supplied paths into the images to process
+///
+/// The last path is the output path, if more than one path is supplied.
+/// A single input file is stored to the output path (`thumbnail.png` by default), unless it is an existing directory.
+/// Several inputs, directories or glob patterns are processed as batch and stored into the output path as directory:
+/// * files and the matches of glob patterns keep their file name
+/// * images of a directory keep their path relative to the directory
+///
+/// Images of a batch, which would be stored to the same output path, such as `a/x.jpg` and `b/x.jpg`, are rejected before any image is processed.
+///
+/// The path `-` stands for stdin as single input path and for stdout as output path of a single image.
+///
+/// An output path containing placeholders, such as `out/{stem}_{width}x{height}.{ext}`, is an output template instead.
+/// It is kept as output path of every image and resolved after the pipeline has run.
+///
+/// Returns a new `Vec<Task>` list, or an `Error` if a path could not be resolved
+///
+/// # Arguments
+///
+/// * `paths` - The supplied input paths, followed by the output path
+/// * `recursive` - Whether the subdirectories of supplied directories should be processed as well
+///
+/// # Examples
+/// ```
+/// # use std::path::PathBuf;
+/// # use thumbnailer_cli::batch::collect_tasks;
+/// let paths = vec![String::from("resources/tests"), String::from("out")];
+/// let tasks = collect_tasks(&paths, false).unwrap();
+/// assert_eq!(tasks.len(), 2);
+/// assert_eq!(tasks[0].output, PathBuf::from("out/test.jpg"));
+/// ```
+pub fn collect_tasks(paths: &[String], recursive: bool) -> Result<Vec<Task>> {
+    let (inputs, output) = match paths.split_last() {
+        Some((output, inputs)) if !inputs.is_empty() => (inputs, Some(output)),
+        _ => (paths, None),
+    };
+
+    let template = output
+        .filter(|output| is_template(output))
+        .map(String::as_str);
+    let output_template = match template {
+        Some(template) => Some(OutputTemplate::parse(template)?),
+        None => None,
+    };
+
+    let output_is_dir = output.is_some_and(|output| Path::new(output).is_dir());
+    if inputs.len() == 1
+        && !output_is_dir
+        && !is_pattern(&inputs[0])
+        && !Path::new(&inputs[0]).is_dir()
+    {
+        let output = output.map_or(DEFAULT_FILE_OUT, String::as_str);
+        return Ok(vec![Task {
+            input: PathBuf::from(&inputs[0]),
+            output: PathBuf::from(output),
+            index: 0,
+        }]);
+    }
+
+    if inputs.iter().any(|input| is_stdio(Path::new(input))) {
+        return Err(Error::argument(
+            NAME_FILE_IN,
+            format!("stdin ({}) can only be used as the only input path", STDIO),
+        ));
+    }
+    if output.is_some_and(|output| is_stdio(Path::new(output))) {
+        return Err(Error::argument(
+            NAME_FILE_OUT,
+            format!("stdout ({}) can only take a single image", STDIO),
+        ));
+    }
+
+    let output_dir = match output {
+        Some(output) => PathBuf::from(output),
+        None => return Err(Error::argument(
+            NAME_FILE_OUT,
+            String::from(
+                "an output directory or output template is required to process a batch of images",
+            ),
+        )),
+    };
+
+    let mut tasks = vec![];
+    for input in inputs {
+        if is_pattern(input) {
+            let matches = glob::glob(input)
+                .map_err(|e| Error::argument(NAME_FILE_IN, format!("{}: {}", input, e)))?;
+            for path in matches {
+                let path = path.map_err(|e| Error::Io {
+                    arg: String::from(NAME_FILE_IN),
+                    path: e.path().display().to_string(),
+                    source: e.into(),
+                })?;
+                if path.is_file() {
+                    tasks.push(task_in_dir(&output_dir, None, path));
+                }
+            }
+        } else if Path::new(input).is_dir() {
+            let root = Path::new(input);
+            walk_directory(&output_dir, root, root, recursive, &mut tasks)?;
+        } else {
+            tasks.push(task_in_dir(&output_dir, None, PathBuf::from(input)));
+        }
+    }
+
+    if tasks.is_empty() {
+        return Err(Error::argument(
+            NAME_FILE_IN,
+            format!("no images found in {}", inputs.join(", ")),
+        ));
+    }
+    number_tasks(&mut tasks, template);
+    check_unique_outputs(&tasks, output_template.as_ref())?;
+    Ok(tasks)
+}
+
+/// This function checks, whether the images of a batch are stored to different output paths
+///
+/// An output template is resolved without the values of the stored image, so `{width}` and `{height}` do not tell the images apart.
+/// A template containing `{hash}` is not checked, as images with the same hash have the same content.
+///
+/// Returns `()`, or an `Error::Argument` naming the first two images, which would overwrite each other
+///
+/// # Arguments
+///
+/// * `tasks` - The list of tasks of the batch
+/// * `template` - The output template of the batch, if any
+fn check_unique_outputs(tasks: &[Task], template: Option<&OutputTemplate>) -> Result<()> {
+    if template.is_some_and(OutputTemplate::needs_hash) {
+        return Ok(());
+    }
+
+    let mut outputs: HashMap<PathBuf, &Path> = HashMap::new();
+    for task in tasks {
+        let output = match template {
+            Some(template) => template.resolve(&task.input, task.index, None),
+            None => task.output.clone(),
+        };
+        if let Some(other) = outputs.insert(output.clone(), &task.input) {
+            let hint = match template {
+                Some(_) => ", add {index}, {dir} or {hash} to the output template",
+                None => "",
+            };
+            return Err(Error::argument(
+                NAME_FILE_OUT,
+                format!(
+                    "the images {} and {} would both be stored to {}{}",
+                    other.display(),
+                    task.input.display(),
+                    output.display(),
+                    hint
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// This function checks, whether the renditions of the images of a batch are stored to different output paths
+///
+/// The path of each rendition is placed next to the output path of each image, so a fixed path, such as `small.png`,
+/// would be stored once for every image of the batch. As for the output template, `{width}` and `{height}` do not tell the images apart
+/// and paths containing `{hash}` are not checked.
+///
+/// Returns `()`, or an `Error::Argument` naming the first two renditions, which would overwrite each other
+///
+/// # Arguments
+///
+/// * `tasks` - The list of tasks of the batch
+/// * `renditions` - The renditions, which are stored for each image
+pub fn check_unique_renditions(tasks: &[Task], renditions: &[Rendition]) -> Result<()> {
+    let mut outputs: HashMap<PathBuf, (&Path, &str)> = HashMap::new();
+    for task in tasks {
+        let output_dir = task.output.parent().unwrap_or_else(|| Path::new(""));
+        for rendition in renditions {
+            let output = output_dir.join(&rendition.output);
+            let output = match output.to_str().filter(|output| is_template(output)) {
+                Some(template) => {
+                    let template = OutputTemplate::parse(template)?;
+                    if template.needs_hash() {
+                        continue;
+                    }
+                    template.resolve(&task.input, task.index, None)
+                }
+                None => output,
+            };
+
+            let rendition_of = (task.input.as_path(), rendition.name.as_str());
+            if let Some((other, name)) = outputs.insert(output.clone(), rendition_of) {
+                return Err(Error::argument(
+                    NAME_FILE_OUT,
+                    format!(
+                        "the rendition {} of {} and the rendition {} of {} would both be stored to {}, add {{stem}}, {{index}} or {{hash}} to the path of the rendition",
+                        name,
+                        other.display(),
+                        rendition.name,
+                        task.input.display(),
+                        output.display()
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// This function resolves the images of the supplied directory, which is watched for new images
+///
+/// The images keep their path relative to the directory within the output directory, unless the output path is an output template.
+///
+/// Returns a new `Vec<Task>` list, which may be empty, or an `Error::Io` if the directory could not be read
+///
+/// # Arguments
+///
+/// * `input_dir` - The watched input directory
+/// * `output` - The output directory or output template
+/// * `recursive` - Whether the subdirectories should be processed as well
+pub fn collect_directory(input_dir: &Path, output: &str, recursive: bool) -> Result<Vec<Task>> {
+    let mut tasks = vec![];
+    walk_directory(
+        Path::new(output),
+        input_dir,
+        input_dir,
+        recursive,
+        &mut tasks,
+    )?;
+
+    let template = Some(output).filter(|output| is_template(output));
+    number_tasks(&mut tasks, template);
+    Ok(tasks)
+}
+
+/// This function assigns the position in the batch to each task and the output template, if it is supplied
+///
+/// # Arguments
+///
+/// * `tasks` - The list of tasks in the order of the batch
+/// * `template` - The output template, which is kept as output path of every task
+fn number_tasks(tasks: &mut [Task], template: Option<&str>) {
+    for (index, task) in tasks.iter_mut().enumerate() {
+        task.index = index;
+        if let Some(template) = template {
+            task.output = PathBuf::from(template);
+        }
+    }
+}
+
+/// This function checks, whether the supplied input path is a glob pattern
+///
+/// # Arguments
+///
+/// * `input` - The supplied input path
+fn is_pattern(input: &str) -> bool {
+    input.contains(&['*', '?', '['][..])
+}
+
+/// This function creates the `Task` for an image of a batch
+///
+/// # Arguments
+///
+/// * `output_dir` - The output directory of the batch
+/// * `root` - The supplied directory containing the image, or `None` to keep only the file name
+/// * `input` - The path of the image
+fn task_in_dir(output_dir: &Path, root: Option<&Path>, input: PathBuf) -> Task {
+    let relative = match root.and_then(|root| input.strip_prefix(root).ok()) {
+        Some(relative) => relative.to_path_buf(),
+        None => PathBuf::from(input.file_name().unwrap_or_default()),
+    };
+
+    Task {
+        output: output_dir.join(relative),
+        input,
+        index: 0,
+    }
+}
+
+/// This function adds all images of a directory to the supplied list, in alphabetical order
+///
+/// Only files with an extension known as image format are added.
+///
+/// # Arguments
+///
+/// * `output_dir` - The output directory of the batch
+/// * `root` - The supplied directory
+/// * `dir` - The directory to read, either `root` or one of its subdirectories
+/// * `recursive` - Whether subdirectories should be read as well
+/// * `tasks` - The list to add the images to
+fn walk_directory(
+    output_dir: &Path,
+    root: &Path,
+    dir: &Path,
+    recursive: bool,
+    tasks: &mut Vec<Task>,
+) -> Result<()> {
+    let io_error = |source| Error::Io {
+        arg: String::from(NAME_FILE_IN),
+        path: dir.display().to_string(),
+        source,
+    };
+
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()
+        })
+        .map_err(io_error)?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if recursive {
+                walk_directory(output_dir, root, &path, recursive, tasks)?;
+            }
+        } else if ImageFormat::from_path(&path).is_ok() {
+            tasks.push(task_in_dir(output_dir, Some(root), path));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{output_format, Encoding};
+
+    /// This function returns the supplied paths as `Vec<String>`
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| String::from(*path)).collect()
+    }
+
+    #[test]
+    fn files_of_a_batch_keep_their_file_name() {
+        let tasks = collect_tasks(&paths(&["a/x.jpg", "b/y.jpg", "out"]), false).unwrap();
+        assert_eq!(tasks[0].output, PathBuf::from("out/x.jpg"));
+        assert_eq!(tasks[1].output, PathBuf::from("out/y.jpg"));
+    }
+
+    #[test]
+    fn files_of_a_batch_with_the_same_file_name_are_rejected() {
+        match collect_tasks(&paths(&["a/x.jpg", "b/x.jpg", "out"]), false) {
+            Err(Error::Argument { arg, message }) => {
+                assert_eq!(arg, NAME_FILE_OUT);
+                assert!(message.contains("a/x.jpg and b/x.jpg"), "{}", message);
+            }
+            _ => panic!("expected an error for the same output path"),
+        }
+    }
+
+    #[test]
+    fn templates_resolving_to_the_same_output_path_are_rejected() {
+        let tasks = collect_tasks(&paths(&["a/x.jpg", "b/x.jpg", "out/{stem}.png"]), false);
+        assert!(tasks.is_err());
+        let tasks = collect_tasks(
+            &paths(&["a/x.jpg", "b/x.jpg", "out/{stem}_{width}.png"]),
+            false,
+        );
+        assert!(tasks.is_err());
+    }
+
+    #[test]
+    fn templates_telling_the_images_apart_are_accepted() {
+        for template in &[
+            "out/{stem}_{index}.png",
+            "{dir}/thumbs/{stem}.png",
+            "out/{hash}.png",
+        ] {
+            let tasks = collect_tasks(&paths(&["a/x.jpg", "b/x.jpg", template]), false).unwrap();
+            assert_eq!(tasks.len(), 2);
+
+            // The format is known before the image is stored, so the template can be stored
+            let template = OutputTemplate::parse(template).unwrap();
+            let output = template.resolve(&tasks[1].input, tasks[1].index, None);
+            assert!(output_format(&output, None).is_ok(), "{}", output.display());
+        }
+    }
+
+    /// This function returns a rendition with the supplied name and output path
+    fn rendition(name: &str, output: &str) -> Rendition {
+        Rendition {
+            name: String::from(name),
+            output: String::from(output),
+            encoding: Encoding::default(),
+            commands: vec![],
+        }
+    }
+
+    #[test]
+    fn renditions_with_a_fixed_path_are_rejected_in_a_batch() {
+        let tasks = collect_tasks(&paths(&["a/x.jpg", "b/y.jpg", "out"]), false).unwrap();
+        match check_unique_renditions(&tasks, &[rendition("small", "small.png")]) {
+            Err(Error::Argument { message, .. }) => {
+                assert!(
+                    message.contains("a/x.jpg and the rendition small of b/y.jpg"),
+                    "{}",
+                    message
+                );
+                assert!(message.contains("out/small.png"), "{}", message);
+            }
+            _ => panic!("expected an error for the same rendition path"),
+        }
+
+        let single = collect_tasks(&paths(&["a/x.jpg", "out/x.png"]), false).unwrap();
+        assert!(check_unique_renditions(&single, &[rendition("small", "small.png")]).is_ok());
+    }
+
+    #[test]
+    fn renditions_of_the_same_image_with_the_same_path_are_rejected() {
+        let tasks = collect_tasks(&paths(&["a/x.jpg", "out/x.png"]), false).unwrap();
+        let renditions = [rendition("small", "x.png"), rendition("large", "x.png")];
+        assert!(check_unique_renditions(&tasks, &renditions).is_err());
+    }
+
+    #[test]
+    fn renditions_telling_the_images_apart_are_accepted() {
+        let tasks = collect_tasks(&paths(&["a/x.jpg", "b/y.jpg", "out"]), false).unwrap();
+        let renditions = [
+            rendition("small", "{stem}_small.png"),
+            rendition("large", "{index}_large.png"),
+            rendition("hashed", "{hash}.png"),
+        ];
+        assert!(check_unique_renditions(&tasks, &renditions).is_ok());
+    }
+}
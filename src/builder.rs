@@ -0,0 +1,454 @@
+use std::path::{Path, PathBuf};
+
+use thumbnailer::{
+    BoxPosition, Crop, Exif, Orientation, ResampleFilter, Resize, Rotation, Thumbnail,
+};
+
+use crate::batch::{check_unique_renditions, Task};
+use crate::cli::optimize::optimize;
+use crate::cli::preset::create_cmd_list_preset;
+use crate::cli::{Commands, ARG_DRY_RUN, ARG_PRESET, NAME_FILE_IN};
+use crate::commands::{
+    CmdBlur, CmdBrighten, CmdCombine, CmdContrast, CmdCrop, CmdExif, CmdFlip, CmdHuerotate,
+    CmdInvert, CmdResize, CmdResizeFilter, CmdRotate, CmdText, CmdUnsharpen, Command,
+};
+use crate::error::{Error, Result};
+use crate::process::{plan, plan_image, process_image, Progress};
+use crate::report::ImageReport;
+use crate::stdio::is_stdio;
+
+/// Representation of a typed builder of a list of commands as a struct
+///
+/// The commands are applied in the order of the calls, like the arguments of the command line interface.
+/// Errors, such as an unknown preset or a photo of `combine` which could not be loaded, are kept until `build`, `run` or `store` and returned by them, so the calls can be chained.
+///
+/// # Examples
+/// ```
+/// # use std::path::Path;
+/// # use thumbnailer::{BoxPosition, Resize};
+/// # use thumbnailer_cli::PipelineBuilder;
+/// # fn main() -> thumbnailer_cli::Result<()> {
+/// let image = PipelineBuilder::new()
+///     .crop_ratio(4.0, 3.0)
+///     .resize(Resize::Width(800))
+///     .text("(c) ACME", BoxPosition::BottomRight(10, 10))
+///     .run(Path::new("resources/tests/test.jpg"))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct PipelineBuilder {
+    /// Contains the `Commands` struct with the commands added so far
+    cmd_list: Commands,
+    /// Contains the `index` as u32 of the next command
+    index: u32,
+    /// Contains the first error of the added commands, if any
+    error: Option<Error>,
+    /// Contains true, if the commands are optimized by `build`
+    optimize: bool,
+}
+
+impl PipelineBuilder {
+    /// Returns a new `PipelineBuilder` struct without any commands
+    pub fn new() -> Self {
+        PipelineBuilder::default()
+    }
+
+    /// This function adds the supplied implementor of `Command`, such as a command of the library user
+    ///
+    /// The `index` of the command is ignored, the commands are applied in the order of the calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `command` - The implementor of `Command` to add
+    pub fn command(mut self, command: Box<dyn Command>) -> Self {
+        self.cmd_list.commands.push(command);
+        self.index += 1;
+        self
+    }
+
+    /// This function adds a blur-command with the supplied sigma
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `sigma` - The sigma as f32
+    pub fn blur(self, sigma: f32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdBlur::new(index, sigma)))
+    }
+
+    /// This function adds a brighten-command with the supplied value
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `value` - The value as i32, negative values darken the image
+    pub fn brighten(self, value: i32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdBrighten::new(index, value)))
+    }
+
+    /// This function adds a contrast-command with the supplied value
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `value` - The value as f32, negative values decrease the contrast
+    pub fn contrast(self, value: f32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdContrast::new(index, value)))
+    }
+
+    /// This function adds a huerotate-command with the supplied degree
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `degree` - The degree as i32
+    pub fn huerotate(self, degree: i32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdHuerotate::new(index, degree)))
+    }
+
+    /// This function adds an invert-command
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    pub fn invert(self) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdInvert::new(index)))
+    }
+
+    /// This function adds an unsharpen-command with the supplied sigma and threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `sigma` - The sigma as f32
+    /// * `threshold` - The threshold as i32
+    pub fn unsharpen(self, sigma: f32, threshold: i32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdUnsharpen::new(index, sigma, threshold)))
+    }
+
+    /// This function adds an exif-command with the supplied option
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `metadata` - The `Exif` enum as option
+    pub fn exif(self, metadata: Exif) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdExif::new(index, metadata)))
+    }
+
+    /// This function adds a crop-command, which crops the image to the supplied box
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `x` - The horizontal offset as u32
+    /// * `y` - The vertical offset as u32
+    /// * `width` - The width as u32
+    /// * `height` - The height as u32
+    pub fn crop_box(self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdCrop::new(
+            index,
+            Crop::Box(x, y, width, height),
+        )))
+    }
+
+    /// This function adds a crop-command, which crops the image to the supplied ratio
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `x_ratio` - The horizontal part of the ratio as f32
+    /// * `y_ratio` - The vertical part of the ratio as f32
+    pub fn crop_ratio(self, x_ratio: f32, y_ratio: f32) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdCrop::new(index, Crop::Ratio(x_ratio, y_ratio))))
+    }
+
+    /// This function adds a flip-command with the supplied orientation
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `orientation` - The `Orientation` enum as option
+    pub fn flip(self, orientation: Orientation) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdFlip::new(index, orientation)))
+    }
+
+    /// This function adds a rotate-command with the supplied rotation
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `rotation` - The `Rotation` enum as option
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdRotate::new(index, rotation)))
+    }
+
+    /// This function adds a resize-command with the supplied size
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `size` - The `Resize` enum as option
+    pub fn resize(self, size: Resize) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdResize::new(index, size)))
+    }
+
+    /// This function adds a resize-command with the supplied size and resample filter
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `size` - The `Resize` enum as option
+    /// * `filter` - The `ResampleFilter` enum as option
+    pub fn resize_filter(self, size: Resize, filter: ResampleFilter) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdResizeFilter::new(index, size, filter)))
+    }
+
+    /// This function adds a text-command with the supplied text and position
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `text` - The text to insert
+    /// * `position` - The `BoxPosition` enum as option
+    pub fn text(self, text: &str, position: BoxPosition) -> Self {
+        let index = self.index;
+        self.command(Box::new(CmdText::new(index, String::from(text), position)))
+    }
+
+    /// This function adds a combine-command, which inserts the photo with the supplied path at the supplied position
+    ///
+    /// The photo is loaded immediately. If it could not be loaded, the `Error::Load` is returned by `build` or `run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `image` - The path of the photo to insert
+    /// * `position` - The `BoxPosition` enum as option
+    pub fn combine(self, image: &str, position: BoxPosition) -> Self {
+        match CmdCombine::load(self.index, image, position, "combine") {
+            Ok(combine) => self.command(Box::new(combine)),
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// This function adds the commands of the preset with the supplied name, like `--preset`
+    ///
+    /// The renditions and the encoder settings of the preset are applied by `store`, `run` returns an error for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `name` - The name of the preset
+    /// * `arguments` - The parameters of the preset as `name=value`
+    pub fn preset(mut self, name: &str, arguments: &[&str]) -> Self {
+        match create_cmd_list_preset(name, arguments, self.index) {
+            Ok(mut preset) => {
+                self.cmd_list.commands.append(&mut preset.commands);
+                self.cmd_list.renditions.append(&mut preset.renditions);
+                self.cmd_list.encoding = preset.encoding.or(&self.cmd_list.encoding);
+                self.index += 1;
+                self
+            }
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// This function keeps the supplied error, unless an earlier error is kept already
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `error` - The `Error` of the added command
+    fn fail(mut self, error: Error) -> Self {
+        self.error.get_or_insert(error);
+        self.index += 1;
+        self
+    }
+
+    /// This function optimizes the commands on `build`, like `--optimize`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    pub fn optimize(mut self) -> Self {
+        self.optimize = true;
+        self
+    }
+
+    /// This function converts the builder into a `Commands` struct, as returned by `read_commands`
+    ///
+    /// Returns the `Commands` struct, or the first `Error` of the added commands
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    pub fn build(self) -> Result<Commands> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut cmd_list = self.cmd_list;
+        if self.optimize {
+            optimize(&mut cmd_list);
+        }
+        Ok(cmd_list)
+    }
+
+    /// This function loads the image with the supplied path and applies all commands in the order of the calls
+    ///
+    /// The commands are checked against the dimensions of the image first, like `--dry-run`, so a crop box outside of the image is returned as `Error::Bounds` instead of panicking.
+    /// Renditions and encoder settings of a preset are only applied by `store`, so they are returned as `Error::Argument`.
+    ///
+    /// Returns the modified `Thumbnail`, or the first `Error` of the added commands
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `input` - The path of the image to load
+    pub fn run(self, input: &Path) -> Result<Thumbnail> {
+        let cmd_list = self.build()?;
+        if !cmd_list.renditions.is_empty() || !cmd_list.encoding.is_empty() {
+            return Err(Error::argument(
+                ARG_PRESET,
+                String::from("renditions and encoder settings require an output, use store"),
+            ));
+        }
+
+        let file_in = input.display().to_string();
+        let load_error = || Error::Load {
+            arg: String::from(NAME_FILE_IN),
+            path: file_in.clone(),
+        };
+        let dimensions = image::image_dimensions(input).map_err(|_| load_error())?;
+        let plan = plan(&cmd_list, dimensions, &Progress::quiet());
+        if !plan.problems.is_empty() {
+            return Err(Error::Bounds {
+                arg: String::from(ARG_DRY_RUN),
+                path: file_in,
+                problems: plan.problems,
+            });
+        }
+
+        let mut image = Thumbnail::load(input.to_path_buf()).map_err(|_| load_error())?;
+        for command in &cmd_list.commands {
+            command.execute(&mut image);
+        }
+        Ok(image)
+    }
+
+    /// This function processes the image with the supplied path like the command line interface and stores the result
+    ///
+    /// The commands are checked against the dimensions of the image first, like `--dry-run`.
+    /// Each rendition of a preset is stored next to the output path, using the encoder settings of the preset.
+    ///
+    /// Returns the `ImageReport` of the image, or the first `Error` of the added commands
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `PipelineBuilder` struct
+    /// * `input` - The path of the image to load
+    /// * `output` - The path of the image to store, which may be an output template
+    pub fn store(self, input: &Path, output: &Path) -> Result<ImageReport> {
+        let cmd_list = self.build()?;
+        if is_stdio(input) {
+            return Err(Error::argument(
+                NAME_FILE_IN,
+                String::from("stdin is not supported by the builder"),
+            ));
+        }
+
+        let task = Task {
+            input: input.to_path_buf(),
+            output: PathBuf::from(output),
+            index: 0,
+        };
+        check_unique_renditions(std::slice::from_ref(&task), &cmd_list.renditions)?;
+        let progress = Progress::quiet();
+        plan_image(&task, &cmd_list, &progress)?;
+        process_image(&task, &cmd_list, None, &progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::Rendition;
+
+    use super::*;
+
+    /// The path of the test image of 500x138 pixels
+    const INPUT: &str = "resources/tests/test.jpg";
+
+    #[test]
+    fn commands_outside_of_the_image_are_returned_as_bounds() {
+        // The crop box fits the image of 500x138 pixels, but not the rotated one of 138x500 pixels
+        let result = PipelineBuilder::new()
+            .rotate(Rotation::Rotate90)
+            .crop_box(200, 0, 100, 100)
+            .run(Path::new(INPUT));
+        match result {
+            Err(Error::Bounds {
+                arg,
+                path,
+                problems,
+            }) => {
+                assert_eq!(arg, ARG_DRY_RUN);
+                assert_eq!(path, INPUT);
+                assert_eq!(problems.len(), 1);
+            }
+            _ => panic!("expected an error for a crop box outside of the image"),
+        }
+    }
+
+    #[test]
+    fn renditions_and_encoder_settings_are_rejected_by_run() {
+        let mut builder = PipelineBuilder::new().blur(1.0);
+        builder.cmd_list.renditions.push(Rendition {
+            name: String::from("small"),
+            output: String::from("small.png"),
+            encoding: Default::default(),
+            commands: vec![],
+        });
+        let mut encoded = PipelineBuilder::new().blur(1.0);
+        encoded.cmd_list.encoding.quality = Some(80);
+
+        for builder in [builder, encoded] {
+            match builder.run(Path::new(INPUT)) {
+                Err(Error::Argument { arg, message }) => {
+                    assert_eq!(arg, ARG_PRESET);
+                    assert_eq!(
+                        message,
+                        "renditions and encoder settings require an output, use store"
+                    );
+                }
+                _ => panic!("expected an error for renditions and encoder settings"),
+            }
+        }
+    }
+
+    #[test]
+    fn first_error_of_the_added_commands_is_returned() {
+        let result = PipelineBuilder::new()
+            .preset("missing", &[])
+            .combine("missing.png", BoxPosition::TopLeft(0, 0))
+            .run(Path::new(INPUT));
+        match result {
+            Err(Error::Argument { arg, .. }) => assert_eq!(arg, ARG_PRESET),
+            _ => panic!("expected an error for an unknown preset"),
+        }
+    }
+}
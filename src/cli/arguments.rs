@@ -1,13 +1,17 @@
+use std::env;
+use std::ffi::OsString;
+
 use clap::{App, Arg};
 
 use crate::cli::{
-    ARG_BLUR, ARG_BRIGHTEN, ARG_COMBINE_BL, ARG_COMBINE_BR, ARG_COMBINE_TL, ARG_COMBINE_TR,
-    ARG_CONTRAST, ARG_CROP_BOX, ARG_CROP_RATIO, ARG_EXIF, ARG_FLIP_HORIZONTAL, ARG_FLIP_VERTICAL,
-    ARG_HUEROTATE, ARG_INVERT, ARG_PRESET, ARG_RESIZE, ARG_RESIZE_C, ARG_RESIZE_G, ARG_RESIZE_L,
-    ARG_RESIZE_N, ARG_RESIZE_T, ARG_ROTATE180, ARG_ROTATE270, ARG_ROTATE90, ARG_TEXT_BL,
-    ARG_TEXT_BR, ARG_TEXT_TL, ARG_TEXT_TR, ARG_UNSHARPEN, NAME_FILE_IN, NAME_FILE_OUT, PRESETS,
-    VAL_COMBINE, VAL_RESIZE, VAL_TEXT,
+    ARG_DRY_RUN, ARG_EMIT_PIPELINE, ARG_FORMAT, ARG_INCREMENTAL, ARG_INTERVAL, ARG_LIST_PRESETS,
+    ARG_JOBS, ARG_MAX_BYTES, ARG_OPS, ARG_OPTIMIZE, ARG_PIPELINE, ARG_PNG_COMPRESSION,
+    ARG_PNG_FILTER, ARG_PROFILE, ARG_PRESET, ARG_PRESET_PARAM, ARG_QUALITY, ARG_RECURSIVE, ARG_REPORT, ARG_SAVE_STEPS,
+    ARG_WATCH, NAME_PATHS,
 };
+use crate::commands::registry::{find_shorthand, operations, shorthands, Operation};
+use crate::format::{FORMATS, PNG_COMPRESSIONS, PNG_FILTERS};
+use crate::report::REPORT_FORMATS;
 
 /// This function bundles the definition of the command line arguments as provided by clap
 ///
@@ -19,181 +23,309 @@ use crate::cli::{
 ///
 /// Returns a new `clap::ArgMatches` struct
 pub fn get_matches() -> clap::ArgMatches<'static> {
-    App::new(env!("CARGO_PKG_NAME"))
+    get_matches_from(env::args_os())
+}
+
+/// This function parses the supplied command line arguments, instead of the ones of the process
+///
+/// Returns a new `clap::ArgMatches` struct
+///
+/// # Arguments
+///
+/// * `args` - The command line arguments, starting with the name of the binary
+///
+/// # Examples
+/// ```
+/// # use thumbnailer_cli::cli::arguments::get_matches_from;
+/// let matches = get_matches_from(vec!["thumbnailer_cli", "--blur", "2.5", "--", "in.png"]);
+/// assert!(matches.is_present("blur"));
+/// ```
+pub fn get_matches_from<I, T>(args: I) -> clap::ArgMatches<'static>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let ops_help = ops_help();
+    let mut app = App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
-        .arg(Arg::with_name(NAME_FILE_IN)
+        .after_help("EXIT CODES:\n    0    Success\n    1    Invalid usage of the command line arguments\n    2    A value of an argument could not be parsed\n    3    An image could not be loaded\n    4    The image could not be stored\n    5    A file format is not supported\n    6    Reading or writing a file failed\n    7    Some images of a batch could not be processed\n    8    The image does not fit into the maximum file size\n    9    The commands do not fit the dimensions of the image (--dry-run)")
+        .arg(Arg::with_name(NAME_PATHS)
             .index(1)
-            .help("Sets the input file as path to use.")
-            .required(true))
-
-        .arg(Arg::with_name(NAME_FILE_OUT)
-            .index(2)
-            .default_value("thumbnail.png")
-            .help("Sets the output file as path to save.")
-            .required(true))
-
-        .arg(Arg::with_name(ARG_BLUR)
-            .long(ARG_BLUR)
-            .value_name("sigma")
-            .help("Performs a Gaussian blur on the supplied image(s). sigma as f32 is a measure of how much to blur by.")
-            .allow_hyphen_values(true)
-            .takes_value(true))
+            .help("Sets the input file(s), directories or glob patterns to use, followed by the output path to save.")
+            .long_help("Sets the input file(s), directories or glob patterns to use, followed by the output path to save. A single input file is saved to the output path, which defaults to thumbnail.png. Several inputs, directories or glob patterns are processed as batch and saved into the output path as directory. An output path containing placeholders is an output template, which is resolved for each image after all commands are applied: {stem}, {ext} and {dir} of the input file, {index} in the batch, {width} and {height} of the result and {hash} of its content, such as out/{stem}_{width}x{height}.{ext}. A single - reads the input image from stdin, and - as output path writes the image to stdout, which requires --format and moves all messages to stderr.")
+            .multiple(true)
+            .required_unless_one(&[ARG_LIST_PRESETS, ARG_EMIT_PIPELINE]))
 
-        .arg(Arg::with_name(ARG_BRIGHTEN)
-            .long(ARG_BRIGHTEN)
-            .value_name("value")
-            .help("Brightens the supplied image(s). value as i32 is the amount to brighten each pixel by. Negative values decrease the brightness and positive values increase it.")
-            .allow_hyphen_values(true)
+        .arg(Arg::with_name(ARG_RECURSIVE)
+            .long(ARG_RECURSIVE)
+            .help("Processes the images in all subdirectories of the supplied directories as well."))
+        .arg(Arg::with_name(ARG_JOBS)
+            .short("j")
+            .long(ARG_JOBS)
+            .value_name("N")
+            .default_value("1")
+            .help("Processes the images of a batch concurrently with N as usize worker threads.")
+            .long_help("Processes the images of a batch concurrently with N as usize worker threads. 0 uses one thread per available CPU core.")
             .takes_value(true))
-
-        .arg(Arg::with_name(ARG_CONTRAST)
-            .long(ARG_CONTRAST)
-            .value_name("contrast")
-            .help("Adjusts the contrast of the supplied image(s). contrast as f32 is the amount to adjust the contrast by. Negative values decrease the contrast and positive values increase the contrast.")
-            .allow_hyphen_values(true)
+        .arg(Arg::with_name(ARG_INCREMENTAL)
+            .long(ARG_INCREMENTAL)
+            .value_name("manifest")
+            .help("Skips images, whose input and commands did not change since the last run, recorded in the given manifest file.")
+            .long_help("Skips images, whose input and commands did not change since the last run and whose outputs still exist. The hashes of the inputs and the commands, including presets and the photos of combine-commands, are recorded in the given manifest file (.json).")
             .takes_value(true))
-
-        .arg(Arg::with_name(ARG_COMBINE_TL)
-            .long(ARG_COMBINE_TL)
-            .value_names(&VAL_COMBINE)
-            .help("Inserts a photo, such as a logo given as path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP LEFT corner of the photo.")
+        .arg(Arg::with_name(ARG_WATCH)
+            .long(ARG_WATCH)
+            .help("Watches the input directory and processes new or modified images into the OUTPUT_PATH.")
+            .long_help("Watches the input directory (the only INPUT_PATH) and processes new or modified images into the OUTPUT_PATH, as soon as their size has stabilised. Processed images are recorded in the state file given by --incremental (.thumbnailer_watch.json within the input directory by default), so they are not processed again after a restart. The messages of the watch mode are printed to stderr."))
+        .arg(Arg::with_name(ARG_INTERVAL)
+            .long(ARG_INTERVAL)
+            .value_name("seconds")
+            .default_value("2")
+            .help("Polls the watched directory every given seconds as u64.")
             .takes_value(true))
-        .arg(Arg::with_name(ARG_COMBINE_TR)
-            .long(ARG_COMBINE_TR)
-            .value_names(&VAL_COMBINE)
-            .help("Inserts a photo, such as a logo given as path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP RIGHT corner of the photo.")
+        .arg(Arg::with_name(ARG_DRY_RUN)
+            .long(ARG_DRY_RUN)
+            .alias("dry-run")
+            .conflicts_with(ARG_WATCH)
+            .help("Simulates all commands on the dimensions of each image, without decoding or storing it.")
+            .long_help("Reads only the header of each image and simulates all commands to plan the dimensions of each step, without decoding or storing any image. Crop boxes, photos and texts outside of the image are reported as errors. The size of a rendered text is not known in advance, so only its anchor, the pixel at its offsets, is checked."))
+        .arg(Arg::with_name(ARG_SAVE_STEPS)
+            .long(ARG_SAVE_STEPS)
+            .alias("save-steps")
+            .value_name("dir")
+            .conflicts_with(ARG_DRY_RUN)
+            .help("Saves the image after each command to the given directory.")
+            .long_help("Saves the loaded image and the image after each command as NN_<command>.png to the given directory, to find the step which breaks the result. The steps of a batch are saved to a subdirectory per image. The commands of pipeline outputs are not saved.")
             .takes_value(true))
-        .arg(Arg::with_name(ARG_COMBINE_BL)
-            .long(ARG_COMBINE_BL)
-            .value_names(&VAL_COMBINE)
-            .help("Inserts a photo, such as a logo given as path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM LEFT corner of the photo.")
+        .arg(Arg::with_name(ARG_PROFILE)
+            .long(ARG_PROFILE)
+            .conflicts_with_all(&[ARG_DRY_RUN, ARG_WATCH])
+            .help("Measures the wall time and peak memory of each step.")
+            .long_help("Measures the wall time of loading each image, of each command and of storing (apply_store) and encoding each output, together with the peak resident memory. Prints a table with the totals and percentiles per step type, or includes it in the report of --report."))
+        .arg(Arg::with_name(ARG_REPORT)
+            .long(ARG_REPORT)
+            .value_name("format")
+            .possible_values(&REPORT_FORMATS)
+            .conflicts_with(ARG_WATCH)
+            .help("Prints a machine-readable report instead of the progress messages.")
+            .long_help("Prints a machine-readable report instead of the progress messages: the input path, format and dimensions of each image, each applied command with its parameters and resulting dimensions, and each output with its path, format and size in bytes, together with the wall time of each step.")
             .takes_value(true))
-        .arg(Arg::with_name(ARG_COMBINE_BR)
-            .long(ARG_COMBINE_BR)
-            .value_names(&VAL_COMBINE)
-            .help("Inserts a photo, such as a logo given as path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM RIGHT corner of the photo.")
+        .arg(Arg::with_name(ARG_FORMAT)
+            .long(ARG_FORMAT)
+            .value_name("format")
+            .possible_values(&FORMATS)
+            .case_insensitive(true)
+            .help("Stores the image(s) in the given format, instead of detecting it by the extension of the output path.")
             .takes_value(true))
-
-        .arg(Arg::with_name(ARG_CROP_BOX)
-            .long(ARG_CROP_BOX)
-            .value_name("x")
-            .value_name("y")
-            .value_name("width")
-            .value_name("height")
-            .help("Crops the supplied image(s) to the given width as u32 and height as u32. x as u32 is the horizontal and y as u32 the vertical offset.")
+        .arg(Arg::with_name(ARG_QUALITY)
+            .long(ARG_QUALITY)
+            .value_name("quality")
+            .help("Sets the quality of the JPEG encoder as u8 from 1 to 100.")
+            .long_help("Sets the quality of the JPEG encoder. quality as u8 from 1 (smallest) to 100 (best) overrides the quality of a preset or pipeline file. The image is encoded again with it, so it can not be combined with --exif.")
             .takes_value(true))
-        .arg(Arg::with_name(ARG_CROP_RATIO)
-            .long(ARG_CROP_RATIO)
-            .value_name("x_ratio")
-            .value_name("y_ratio")
-            .help("Crops the supplied image(s) to the given ratio 'x_ratio:y_ratio'. x_ratio as f32 is representing the horizontal and y_ratio as f32 the vertical.")
-            .allow_hyphen_values(true)
+        .arg(Arg::with_name(ARG_PNG_COMPRESSION)
+            .long(ARG_PNG_COMPRESSION)
+            .alias("png-compression")
+            .value_name("level")
+            .possible_values(&PNG_COMPRESSIONS)
+            .case_insensitive(true)
+            .help("Sets the compression level of the PNG encoder.")
+            .long_help("Sets the compression level of the PNG encoder. The image is encoded again with it, so it can not be combined with --exif.")
             .takes_value(true))
-
-        .arg(Arg::with_name(ARG_EXIF)
-            .long(ARG_EXIF)
-            .help("The metadata of the target file is taken from the source file and not removed as usual."))
-
-        .arg(Arg::with_name(ARG_FLIP_HORIZONTAL)
-            .long(ARG_FLIP_HORIZONTAL)
-            .help("Flip the supplied image(s) horizontally."))
-        .arg(Arg::with_name(ARG_FLIP_VERTICAL)
-            .long(ARG_FLIP_VERTICAL)
-            .help("Flip the supplied image(s) vertically."))
-
-        .arg(Arg::with_name(ARG_HUEROTATE)
-            .long(ARG_HUEROTATE)
-            .value_name("value")
-            .help("Hue rotate the supplied image(s). value as i32 is the degrees to rotate each pixel by. 0 and 360 do nothing, the rest rotates by the given degree value.")
-            .allow_hyphen_values(true)
+        .arg(Arg::with_name(ARG_PNG_FILTER)
+            .long(ARG_PNG_FILTER)
+            .alias("png-filter")
+            .value_name("filter")
+            .possible_values(&PNG_FILTERS)
+            .case_insensitive(true)
+            .help("Sets the filter type of the PNG encoder.")
+            .long_help("Sets the filter type of the PNG encoder. The image is encoded again with it, so it can not be combined with --exif.")
             .takes_value(true))
+        .arg(Arg::with_name(ARG_MAX_BYTES)
+            .long(ARG_MAX_BYTES)
+            .alias("max-bytes")
+            .value_name("bytes")
+            .help("Stores JPEG images with the highest quality, which does not exceed bytes as u64.")
+            .long_help("Stores JPEG images with the highest quality, whose file size does not exceed bytes as u64. The quality is searched from 1 up to --quality (100 by default) and printed. The image is encoded again with it, so it can not be combined with --exif.")
+            .takes_value(true));
 
-        .arg(Arg::with_name(ARG_INVERT)
-            .long(ARG_INVERT)
-            .help("Invert each pixel within the supplied image(s)."))
+    // The arguments of all commands are derived from their operations in the registry
+    for operation in operations() {
+        app = app.arg(operation_arg(operation));
+    }
 
-        .arg(Arg::with_name(ARG_RESIZE)
-            .long(ARG_RESIZE)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. To resize only by one dimension, set the other to 0.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_RESIZE_N)
-            .long(ARG_RESIZE_N)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Nearest is the used filter (Nearest Neighbor Filter). To resize only by one dimension, set the other to 0.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_RESIZE_T)
-            .long(ARG_RESIZE_T)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Triangle is the used filter (Linear Filter). To resize only by one dimension, set the other to 0.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_RESIZE_C)
-            .long(ARG_RESIZE_C)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. CatmullRom is the used filter (Cubic Filter). To resize only by one dimension, set the other to 0.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_RESIZE_G)
-            .long(ARG_RESIZE_G)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Gaussian is the used filter (Gaussian Filter). To resize only by one dimension, set the other to 0.")
+    app
+        .arg(Arg::with_name(ARG_PRESET)
+            .long(ARG_PRESET)
+            .value_name("name")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Performs predefined commands in a given order, based on the preset, which was chosen.")
+            .long_help("Performs predefined commands in a given order, based on the preset, which was chosen. The name may be followed by parameters of the preset as name=value, such as --preset app_copyright owner=ACME width=800, which replace the placeholders {name} of the preset. Several presets may be supplied and mixed with other commands, each expanding at its position. A step op = \"preset\" with the name and the parameters of another preset includes its steps. Presets are defined like pipeline files (.toml) as named tables with an optional description and a table params, declaring the type (string, integer, unsigned, float or boolean) and the optional default of each parameter. The built-in presets app_copyright, full_hd and background are overridden by the presets of the per-user file thumbnailer/presets.toml in the configuration directory, which in turn are overridden by the presets of thumbnailer_presets.toml in the current directory.")
             .takes_value(true))
-        .arg(Arg::with_name(ARG_RESIZE_L)
-            .long(ARG_RESIZE_L)
-            .value_names(&VAL_RESIZE)
-            .help("Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Lanczos3 is the used filter (Lanczos with window 3). To resize only by one dimension, set the other to 0.")
+        .arg(Arg::with_name(ARG_PRESET_PARAM)
+            .long(ARG_PRESET_PARAM)
+            .alias("preset-param")
+            .value_name("name=value")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Sets a parameter of the preceding --preset as name=value.")
+            .long_help("Sets a parameter of the preceding --preset as name=value, which replaces the placeholder {name} of the preset. It is the long form of the parameters following the name of --preset, so --preset app_copyright owner=ACME is the same as --preset app_copyright --preset_param owner=ACME.")
             .takes_value(true))
+        .arg(Arg::with_name(ARG_LIST_PRESETS)
+            .long(ARG_LIST_PRESETS)
+            .alias("list-presets")
+            .help("Lists all available presets.")
+            .long_help("Lists all available presets with their origin, description and the commands and encoder settings they expand to."))
 
-        .arg(Arg::with_name(ARG_ROTATE90)
-            .short("r")
-            .long(ARG_ROTATE90)
-            .help("Rotate the supplied image(s) 90 degrees clockwise.")
-            .multiple(true))
-        .arg(Arg::with_name(ARG_ROTATE180)
-            .long(ARG_ROTATE180)
-            .help("Rotate the supplied image(s) 180 degrees clockwise."))
-        .arg(Arg::with_name(ARG_ROTATE270)
-            .long(ARG_ROTATE270)
-            .help("Rotate the supplied image(s) 270 degrees clockwise."))
-
-        .arg(Arg::with_name(ARG_TEXT_TL)
-            .long(ARG_TEXT_TL)
-            .value_names(&VAL_TEXT)
-            .help("Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP LEFT corner of the photo.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_TEXT_TR)
-            .long(ARG_TEXT_TR)
-            .value_names(&VAL_TEXT)
-            .help("Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP RIGHT corner of the photo.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_TEXT_BL)
-            .long(ARG_TEXT_BL)
-            .value_names(&VAL_TEXT)
-            .help("Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM LEFT corner of the photo.")
-            .takes_value(true))
-        .arg(Arg::with_name(ARG_TEXT_BR)
-            .long(ARG_TEXT_BR)
-            .value_names(&VAL_TEXT)
-            .help("Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM RIGHT corner of the photo.")
+        .arg(Arg::with_name(ARG_OPS)
+            .long(ARG_OPS)
+            .value_name("operations")
+            .help("Performs the given operations in the given order, separated by |.")
+            .long_help(ops_help.as_str())
+            .multiple(true)
+            .number_of_values(1)
             .takes_value(true))
 
-        .arg(Arg::with_name(ARG_UNSHARPEN)
-            .long(ARG_UNSHARPEN)
-            .value_name("sigma")
-            .value_name("threshold")
-            .help("Performs an unsharpen mask on the supplied image(s). sigma as f32 is the amount to unsharpen the image by. threshold as i32 controls the minimal brightness change or how far apart adjacent tonal values have to be before the filter does anything.")
-            .allow_hyphen_values(true)
+        .arg(Arg::with_name(ARG_PIPELINE)
+            .long(ARG_PIPELINE)
+            .value_name("file")
+            .help("Performs the commands listed in the given pipeline file (.toml or .json) in the given order.")
+            .long_help("Performs the commands listed in the given pipeline file (.toml or .json) in the given order. Each entry of the step list names the command by op, such as op = \"blur\", and its values by name, such as sigma = 2.5. Optional named outputs, each with its own path and trailing steps, store several renditions of each image, which is loaded only once. The keys format, quality, png_compression, png_filter and max_bytes set the encoder settings of all outputs or of a single output.")
             .takes_value(true))
 
-        .arg(Arg::with_name(ARG_PRESET)
-            .long(ARG_PRESET)
-            .value_name("name")
-            .possible_values(&PRESETS)
-            .help("Performs predefined commands in a given order, based on the preset, which was chosen.")
+        .arg(Arg::with_name(ARG_OPTIMIZE)
+            .long(ARG_OPTIMIZE)
+            .help("Removes redundant work from the commands, without changing the result.")
+            .long_help("Removes redundant work from the commands, without changing the result: consecutive rotations and flips are folded into a single flip and rotation, commands without effect (brighten 0, huerotate 0, invert twice) are dropped and crops are moved before the directly preceding per-pixel filters (brighten, contrast, huerotate and invert), which then process only the cropped image. The optimized commands are shown by --save-steps, --report and --emit-pipeline.")
+            .takes_value(false))
+
+        .arg(Arg::with_name(ARG_EMIT_PIPELINE)
+            .long(ARG_EMIT_PIPELINE)
+            .alias("emit-pipeline")
+            .value_name("file")
+            .help("Writes all supplied commands as pipeline file (.toml or .json).")
+            .long_help("Writes all supplied commands, after expanding presets and pipeline files, with the encoder settings as pipeline file (.toml or .json) in the order they are applied, so --pipeline file reproduces the same output. Without PATHS, only the file is written.")
             .takes_value(true))
 
-        .get_matches()
-    //to debug combine, use:
-    //.get_matches_from(vec![env!("CARGO_PKG_NAME"), "in.png" ,"--combine_tl", "C:\\Users\\p372094\\IdeaProjects\\thumbnailer_cli\\img\\test.JPG", "40", "30"])
+        .get_matches_from(expand_preset_params(args))
+}
+
+/// This function rewrites the parameters following the name of `--preset` into occurrences of `--preset_param`
+///
+/// The parameters `name=value` directly following the name of a preset belong to it, such as `--preset app_copyright owner=ACME`.
+/// As `--preset` takes a single value, clap would read them as PATHS otherwise.
+/// Arguments after `--` are kept as they are.
+///
+/// Returns a new `Vec<OsString>` list of the arguments
+///
+/// # Arguments
+///
+/// * `args` - The command line arguments, starting with the name of the binary
+///
+/// # Examples
+/// The example is not compiled, as `expand_preset_params` is private.
+///
+/// ```ignore
+/// let args = expand_preset_params(vec!["thumbnailer_cli", "--preset", "app_copyright", "owner=ACME", "in.png"]);
+/// assert_eq!(args, ["thumbnailer_cli", "--preset", "app_copyright", "--preset_param", "owner=ACME", "in.png"]);
+/// ```
+fn expand_preset_params<I, T>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let preset = format!("--{}", ARG_PRESET);
+    let preset_param = format!("--{}", ARG_PRESET_PARAM);
+    let mut expanded: Vec<OsString> = vec![];
+    let mut in_preset = false;
+    let mut args = args.into_iter().map(Into::into);
+    while let Some(arg) = args.next() {
+        let text = arg.to_str().unwrap_or("");
+        if text == "--" {
+            expanded.push(arg);
+            expanded.extend(args);
+            break;
+        }
+        if text == preset {
+            expanded.push(arg);
+            expanded.extend(args.next());
+            in_preset = true;
+            continue;
+        }
+        if text.starts_with(&format!("{}=", preset)) {
+            in_preset = true;
+        } else if in_preset && is_preset_param(text) {
+            expanded.push(OsString::from(&preset_param));
+        } else {
+            in_preset = false;
+        }
+        expanded.push(arg);
+    }
+    expanded
+}
+
+/// This function checks whether the supplied argument is a preset parameter as `name=value`
+///
+/// Returns true, if the name before `=` consists of letters, digits and underscores only
+///
+/// # Arguments
+///
+/// * `arg` - The command line argument
+fn is_preset_param(arg: &str) -> bool {
+    match arg.find('=') {
+        Some(end) if end > 0 => arg[..end]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// This function returns the long help text of `--ops`, listing the shorthands and the operations of the registry
+fn ops_help() -> String {
+    let usages: Vec<&str> = shorthands().map(|shorthand| shorthand.usage).collect();
+    // Operations named like a shorthand are only available by the shorthand
+    let operations: Vec<String> = operations()
+        .filter(|operation| find_shorthand(operation.name).is_none())
+        .map(|operation| {
+            let names: Vec<&str> = operation.parameters.iter().map(|parameter| parameter.name).collect();
+            match names.is_empty() {
+                true => String::from(operation.name),
+                false => format!("{}:{}", operation.name, names.join(",")),
+            }
+        })
+        .collect();
+
+    format!("Performs the given operations in the given order, separated by |, such as \"crop:ratio=4:3 | resize:bbox=600x450,filter=lanczos3 | text:br,20,15,'(c) ACME'\". Each operation is a name followed by : and its values, which are separated by , and supplied by position or as name=value. Text containing , or | is quoted by ' or \". Shorthands: {}. All other commands are available by the name of their argument with the same values: {}.", usages.join(", "), operations.join(", "))
+}
+
+/// This function defines the command line argument of the supplied operation of the registry
+///
+/// Each occurrence of the argument takes one value per parameter of the operation, values of signed parameters may start with a hyphen.
+///
+/// Returns a new `clap::Arg` struct
+///
+/// # Arguments
+///
+/// * `operation` - The `Operation` struct, declaring the name, parameters and help text
+fn operation_arg(operation: &'static Operation) -> Arg<'static, 'static> {
+    let mut arg = Arg::with_name(operation.name)
+        .long(operation.name)
+        .help(operation.help)
+        .multiple(true);
+    if let Some(short) = operation.short {
+        arg = arg.short(short);
+    }
+    if operation.parameters.is_empty() {
+        return arg;
+    }
+
+    for parameter in operation.parameters {
+        arg = arg.value_name(parameter.name);
+    }
+    arg.allow_hyphen_values(operation.parameters.iter().any(|parameter| parameter.kind.is_signed()))
+        .number_of_values(operation.parameters.len() as u64)
+        .takes_value(true)
 }
@@ -2,51 +2,62 @@ pub use arguments::get_matches;
 pub use parser::read_commands;
 
 use crate::commands::Command;
+use crate::format::Encoding;
 
 pub mod arguments;
+pub mod ops;
+pub mod optimize;
 pub mod parser;
+pub mod pipeline;
+pub mod preset;
 
 pub const NAME_FILE_IN: &str = "INPUT_PATH";
 pub const NAME_FILE_OUT: &str = "OUTPUT_PATH";
+pub const NAME_PATHS: &str = "PATHS";
+pub const ARG_RECURSIVE: &str = "recursive";
+pub const ARG_JOBS: &str = "jobs";
+pub const ARG_MAX_BYTES: &str = "max_bytes";
+pub const ARG_INCREMENTAL: &str = "incremental";
+pub const ARG_WATCH: &str = "watch";
+pub const ARG_INTERVAL: &str = "interval";
+pub const ARG_REPORT: &str = "report";
+pub const ARG_DRY_RUN: &str = "dry_run";
+pub const ARG_SAVE_STEPS: &str = "save_steps";
+pub const ARG_PROFILE: &str = "profile";
+pub const ARG_LIST_PRESETS: &str = "list_presets";
+pub const ARG_EMIT_PIPELINE: &str = "emit_pipeline";
+pub const ARG_PRESET: &str = "preset";
 
-const ARG_BLUR: &str = "blur";
-const ARG_BRIGHTEN: &str = "brighten";
-const ARG_CONTRAST: &str = "contrast";
-const ARG_COMBINE_TL: &str = "combine_tl";
-const ARG_COMBINE_TR: &str = "combine_tr";
-const ARG_COMBINE_BL: &str = "combine_bl";
-const ARG_COMBINE_BR: &str = "combine_br";
-const ARG_CROP_BOX: &str = "crop_box";
-const ARG_CROP_RATIO: &str = "crop_ratio";
-const ARG_EXIF: &str = "exif";
-const ARG_FLIP_HORIZONTAL: &str = "flip_horizontal";
-const ARG_FLIP_VERTICAL: &str = "flip_vertical";
-const ARG_HUEROTATE: &str = "huerotate";
-const ARG_INVERT: &str = "invert";
-const ARG_RESIZE: &str = "resize";
-const ARG_RESIZE_N: &str = "resize_n";
-const ARG_RESIZE_T: &str = "resize_t";
-const ARG_RESIZE_C: &str = "resize_c";
-const ARG_RESIZE_G: &str = "resize_g";
-const ARG_RESIZE_L: &str = "resize_l";
-const ARG_ROTATE90: &str = "rotate90";
-const ARG_ROTATE180: &str = "rotate180";
-const ARG_ROTATE270: &str = "rotate270";
-const ARG_TEXT_TL: &str = "text_tl";
-const ARG_TEXT_TR: &str = "text_tr";
-const ARG_TEXT_BL: &str = "text_bl";
-const ARG_TEXT_BR: &str = "text_br";
-const ARG_UNSHARPEN: &str = "unsharpen";
-const ARG_PRESET: &str = "preset";
-
-const VAL_COMBINE: [&str; 3] = ["IMAGE_PATH", "x_offset", "y_offset"];
-const VAL_RESIZE: [&str; 3] = ["nwidth", "nheight", "exact"];
-const VAL_TEXT: [&str; 3] = ["text", "x_offset", "y_offset"];
-
-const PRESETS: [&str; 3] = ["app_copyright", "full_hd", "background"];
+const ARG_PRESET_PARAM: &str = "preset_param";
+const ARG_PIPELINE: &str = "pipeline";
+const ARG_OPS: &str = "ops";
+const ARG_OPTIMIZE: &str = "optimize";
+const ARG_FORMAT: &str = "format";
+const ARG_QUALITY: &str = "quality";
+const ARG_PNG_COMPRESSION: &str = "png_compression";
+const ARG_PNG_FILTER: &str = "png_filter";
 
 /// Representation of the command-list as a struct
+#[derive(Default)]
 pub struct Commands {
     /// Contains the implementors of `Command` to apply a list of operations, which are provided by `thumbnailer`, on the supplied image(s)
-    pub(crate) commands: Vec<Box<dyn Command>>,
+    pub commands: Vec<Box<dyn Command>>,
+    /// Contains the named outputs of the image, which are stored instead of the single output path, if any
+    pub renditions: Vec<Rendition>,
+    /// Contains the output format and the encoder settings, such as `--format` and `--quality`
+    pub encoding: Encoding,
+}
+
+/// Representation of a named output of the image as a struct
+///
+/// The commands of a rendition are applied to a copy of the image after all commands of the `Commands` struct.
+pub struct Rendition {
+    /// Contains the name of the rendition
+    pub name: String,
+    /// Contains the output path or output template, which is placed next to the output path of the image if it is relative
+    pub output: String,
+    /// Contains the output format and the encoder settings of the rendition, which take precedence over the ones of `Commands`
+    pub encoding: Encoding,
+    /// Contains the implementors of `Command`, which are only applied to this rendition
+    pub commands: Vec<Box<dyn Command>>,
 }
@@ -0,0 +1,626 @@
+use crate::cli::ARG_OPS;
+use crate::commands::registry::{
+    find_operation, find_shorthand, operation_names, shorthands, Operation, Shorthand,
+};
+use crate::commands::Command;
+use crate::error::{Error, Result};
+
+/// Representation of an error of the `--ops` language as a struct, pointing at the column it occurred in
+struct OpsError {
+    /// Contains the column of the error, starting at 1
+    column: usize,
+    /// Contains the description of the error
+    message: String,
+}
+
+/// Result type of the `--ops` language, using `OpsError` as error
+type OpsResult<T> = std::result::Result<T, OpsError>;
+
+/// Representation of a single value of an operation as a struct, such as `20` or `filter=lanczos3`
+struct Value {
+    /// Contains the name of a named value, or `None` for a positional value
+    key: Option<String>,
+    /// Contains the value without quotes
+    value: String,
+    /// Contains the column, at which the value (after the name of a named value) starts
+    column: usize,
+    /// Contains the column, at which the name of a named value or the positional value starts
+    key_column: usize,
+}
+
+/// Representation of a single operation as a struct, such as `resize:bbox=600x450`
+struct Op {
+    /// Contains the name of the operation
+    name: String,
+    /// Contains the values of the operation in the supplied order
+    values: Vec<Value>,
+    /// Contains the column, at which the operation starts
+    column: usize,
+}
+
+/// This function parses the supplied operations into commands
+///
+/// The operations are separated by `|` and applied in the supplied order.
+/// Each operation is a name, optionally followed by `:` and its values, which are separated by `,`.
+/// A value is positional or named as `name=value`, text containing `,` or `|` is quoted by `'` or `"`:
+/// `crop:ratio=4:3 | resize:bbox=600x450,filter=lanczos3 | text:br,20,15,'(c) ACME'`
+///
+/// Besides the shorthands of the registry, such as `crop` for `crop_box` and `crop_ratio`, each operation of the registry is available by its name with the names of its parameters, such as `crop_box:10,10,200,100`.
+///
+/// All commands get the supplied `index`, so they keep their order and are placed at the position of `--ops`.
+///
+/// Returns a new `Vec<Box<dyn Command>>` list, or an `Error::Argument` pointing at the column where parsing failed
+///
+/// # Arguments
+///
+/// * `ops` - The supplied operations
+/// * `index` - The `index` as u32 of arguments list, at which the operations were supplied
+///
+/// # Examples
+/// ```
+/// # use thumbnailer_cli::cli::ops::create_cmd_list_ops;
+/// let commands = create_cmd_list_ops("crop:ratio=4:3 | resize:width=600 | blur:1.5", 3).unwrap();
+/// assert_eq!(commands.len(), 3);
+/// assert!(create_cmd_list_ops("crop:ratio=4:3 | rezise:width=600", 3).is_err());
+/// ```
+pub fn create_cmd_list_ops(ops: &str, index: u32) -> Result<Vec<Box<dyn Command>>> {
+    let to_error = |error: OpsError| {
+        Error::argument(
+            ARG_OPS,
+            format!(
+                "{} at column {}\n    {}\n    {}^",
+                error.message,
+                error.column,
+                ops,
+                " ".repeat(error.column - 1)
+            ),
+        )
+    };
+
+    let mut parser = OpsParser {
+        chars: ops.chars().collect(),
+        position: 0,
+    };
+    parser
+        .parse()
+        .map_err(to_error)?
+        .into_iter()
+        .map(|op| op.to_command(index).map_err(to_error))
+        .collect()
+}
+
+/// Representation of the state of the parser of the `--ops` language as a struct
+struct OpsParser {
+    /// Contains the characters of the supplied operations
+    chars: Vec<char>,
+    /// Contains the position of the next character
+    position: usize,
+}
+
+impl OpsParser {
+    /// This function parses all operations
+    ///
+    /// Returns a new `Vec<Op>` list, or an `OpsError` pointing at the first unexpected character
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - The `OpsParser` struct
+    fn parse(&mut self) -> OpsResult<Vec<Op>> {
+        let mut ops = vec![];
+        loop {
+            self.skip_whitespace();
+            let column = self.column();
+            let name = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            if name.is_empty() {
+                return Err(self.unexpected("the name of an operation"));
+            }
+
+            let mut values = vec![];
+            self.skip_whitespace();
+            if self.peek() == Some(':') {
+                self.position += 1;
+                loop {
+                    values.push(self.parse_value()?);
+                    self.skip_whitespace();
+                    if self.peek() != Some(',') {
+                        break;
+                    }
+                    self.position += 1;
+                }
+            }
+            ops.push(Op {
+                name,
+                values,
+                column,
+            });
+
+            match self.peek() {
+                None => return Ok(ops),
+                Some('|') => self.position += 1,
+                Some(_) => return Err(self.unexpected(": or |")),
+            }
+        }
+    }
+
+    /// This function parses a single positional or named value
+    ///
+    /// Returns the `Value`, or an `OpsError` if the value is empty or its quote is not closed
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - The `OpsParser` struct
+    fn parse_value(&mut self) -> OpsResult<Value> {
+        self.skip_whitespace();
+        let key_column = self.column();
+        let start = self.position;
+
+        let name = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        let key = match self.peek() {
+            Some('=') if !name.is_empty() => {
+                self.position += 1;
+                Some(name)
+            }
+            _ => {
+                self.position = start;
+                None
+            }
+        };
+
+        let column = self.column();
+        let value = match self.peek() {
+            Some(quote @ '\'') | Some(quote @ '"') => {
+                let quote_column = self.column();
+                self.position += 1;
+                let value = self.read_while(|c| c != quote);
+                if self.peek() != Some(quote) {
+                    return Err(OpsError {
+                        column: quote_column,
+                        message: format!("the quote {} is not closed", quote),
+                    });
+                }
+                self.position += 1;
+
+                // Only the separators may follow the closing quote, anything else would be lost
+                self.skip_whitespace();
+                if !matches!(self.peek(), None | Some(',') | Some('|')) {
+                    return Err(self.unexpected(", or | or end of input after the quoted value"));
+                }
+                value
+            }
+            _ => {
+                let value = self.read_while(|c| c != ',' && c != '|');
+                let value = String::from(value.trim_end());
+                if value.is_empty() {
+                    return Err(self.unexpected("a value"));
+                }
+                value
+            }
+        };
+
+        Ok(Value {
+            key,
+            value,
+            column,
+            key_column,
+        })
+    }
+
+    /// This function returns the next character without consuming it, or `None` at the end
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OpsParser` struct
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    /// This function returns the column of the next character, starting at 1
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OpsParser` struct
+    fn column(&self) -> usize {
+        self.position + 1
+    }
+
+    /// This function consumes all characters, as long as they match the supplied predicate
+    ///
+    /// Returns the consumed characters as String
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - The `OpsParser` struct
+    /// * `predicate` - The predicate the characters have to match
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// This function consumes all whitespace
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - The `OpsParser` struct
+    fn skip_whitespace(&mut self) {
+        self.read_while(char::is_whitespace);
+    }
+
+    /// This function returns an `OpsError` for the next character, which does not match the expectation
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OpsParser` struct
+    /// * `expected` - The description of the expected input
+    fn unexpected(&self, expected: &str) -> OpsError {
+        let found = match self.peek() {
+            Some(c) => format!("{}", c),
+            None => String::from("end of input"),
+        };
+        OpsError {
+            column: self.column(),
+            message: format!("expected {}, found {}", expected, found),
+        }
+    }
+}
+
+impl Op {
+    /// This function converts the operation into the command of the matching operation of the registry
+    ///
+    /// A shorthand, such as `crop`, selects one of several operations of the registry by its values, all other names are looked up in the registry directly.
+    ///
+    /// Returns a new `Box<dyn Command>`, or an `OpsError` pointing at the invalid operation or value
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Op` struct
+    /// * `index` - The `index` as u32 of arguments list, at which the operations were supplied
+    fn to_command(&self, index: u32) -> OpsResult<Box<dyn Command>> {
+        let (operation, values) = match find_shorthand(&self.name) {
+            Some(shorthand) => self.expand(shorthand)?,
+            None => self.registry_values()?,
+        };
+
+        operation
+            .command(index, ARG_OPS, values)
+            .map_err(|error| match error {
+                Error::Argument { message, .. } => OpsError {
+                    column: self.column,
+                    message,
+                },
+                Error::Load { path, .. } => OpsError {
+                    column: self
+                        .values
+                        .iter()
+                        .find(|value| value.value == path)
+                        .map_or(self.column, |value| value.column),
+                    message: format!("failed to load the image {}", path),
+                },
+                error => OpsError {
+                    column: self.column,
+                    message: error.to_string(),
+                },
+            })
+    }
+
+    /// This function selects the operation of the registry and its values by the supplied shorthand
+    ///
+    /// Returns the `Operation` struct and its values, or an `OpsError` pointing at the invalid operation or value
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Op` struct
+    /// * `shorthand` - The `Shorthand` struct with the name of the operation
+    fn expand(
+        &self,
+        shorthand: &'static Shorthand,
+    ) -> OpsResult<(&'static Operation, Vec<String>)> {
+        let names: Vec<&str> = shorthand
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name)
+            .collect();
+        let values = self.assign(&names)?;
+        for (value, parameter) in values.iter().zip(shorthand.parameters) {
+            match value {
+                Some(value) if !parameter.kind.accepts(&value.value) => {
+                    return Err(invalid(value, parameter.kind.name()))
+                }
+                _ => {}
+            }
+        }
+
+        let (name, expanded) = shorthand
+            .operation(
+                values
+                    .iter()
+                    .map(|value| value.map(|value| value.value.as_str()))
+                    .collect(),
+            )
+            .map_err(|error| OpsError {
+                column: error
+                    .parameter
+                    .and_then(|parameter| names.iter().position(|name| *name == parameter))
+                    .and_then(|position| values[position])
+                    .map_or(self.column, |value| value.column),
+                message: error.message,
+            })?;
+        let operation = find_operation(name).ok_or_else(|| OpsError {
+            column: self.column,
+            message: format!("{} expands to the unknown operation {}", self.name, name),
+        })?;
+        Ok((operation, expanded))
+    }
+
+    /// This function looks up the operation of the registry with the same name and assigns the values to its parameters
+    ///
+    /// Omitted parameters get their default value.
+    ///
+    /// Returns the `Operation` struct and its values, or an `OpsError` pointing at the invalid operation or value
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Op` struct
+    fn registry_values(&self) -> OpsResult<(&'static Operation, Vec<String>)> {
+        let operation = find_operation(&self.name).ok_or_else(|| {
+            let mut names = operation_names();
+            names.extend(shorthands().map(|shorthand| shorthand.name));
+            names.sort_unstable();
+            names.dedup();
+            OpsError {
+                column: self.column,
+                message: format!(
+                    "unknown operation {}, expected one of {}",
+                    self.name,
+                    names.join(", ")
+                ),
+            }
+        })?;
+
+        let names: Vec<&str> = operation
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name)
+            .collect();
+        let values = self
+            .assign(&names)?
+            .into_iter()
+            .zip(operation.parameters)
+            .map(|(value, parameter)| match value {
+                Some(value) if !parameter.kind.accepts(&value.value) => {
+                    Err(invalid(value, parameter.kind.name()))
+                }
+                Some(value) => Ok(value.value.clone()),
+                None => parameter.default.map(String::from).ok_or_else(|| OpsError {
+                    column: self.column,
+                    message: format!("{} requires the value {}", self.name, parameter.name),
+                }),
+            })
+            .collect::<OpsResult<Vec<_>>>()?;
+        Ok((operation, values))
+    }
+
+    /// This function assigns the positional and named values of the operation to the supplied parameter names
+    ///
+    /// Positional values are assigned in the order of the names, named values by their name.
+    ///
+    /// Returns the values in the order of the names, or an `OpsError` if a value is unknown, superfluous or supplied twice
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Op` struct
+    /// * `names` - The names of the parameters of the operation
+    fn assign(&self, names: &[&str]) -> OpsResult<Vec<Option<&Value>>> {
+        let mut values = vec![None; names.len()];
+        let mut positional = 0;
+        for value in &self.values {
+            let slot = match &value.key {
+                Some(key) => names
+                    .iter()
+                    .position(|name| name == key)
+                    .ok_or_else(|| OpsError {
+                        column: value.key_column,
+                        message: format!(
+                            "{} has no value {}, expected {}",
+                            self.name,
+                            key,
+                            expected_names(names)
+                        ),
+                    })?,
+                None => {
+                    positional += 1;
+                    if positional > names.len() {
+                        return Err(OpsError {
+                            column: value.key_column,
+                            message: format!(
+                                "too many values, {} expects {}",
+                                self.name,
+                                match names.is_empty() {
+                                    true => String::from("no values"),
+                                    false => names.join(", "),
+                                }
+                            ),
+                        });
+                    }
+                    positional - 1
+                }
+            };
+            if values[slot].is_some() {
+                return Err(OpsError {
+                    column: value.key_column,
+                    message: format!("{} is supplied twice", names[slot]),
+                });
+            }
+            values[slot] = Some(value);
+        }
+        Ok(values)
+    }
+}
+
+/// This function returns the supplied parameter names, to report errors
+///
+/// # Arguments
+///
+/// * `names` - The names of the parameters of the operation
+fn expected_names(names: &[&str]) -> String {
+    match names.is_empty() {
+        true => String::from("no values"),
+        false => format!("one of {}", names.join(", ")),
+    }
+}
+
+/// This function returns an `OpsError` for the supplied value, which does not match the expectation
+///
+/// # Arguments
+///
+/// * `value` - The supplied `Value`
+/// * `expected` - The description of the expected value
+fn invalid(value: &Value, expected: &str) -> OpsError {
+    OpsError {
+        column: value.column,
+        message: format!("expected {}, got {}", expected, value.value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// This function returns the descriptions of the commands of the supplied operations
+    fn describe(ops: &str) -> Vec<(&'static str, serde_json::Value)> {
+        create_cmd_list_ops(ops, 3)
+            .unwrap_or_else(|error| panic!("{}", error))
+            .iter()
+            .map(|command| {
+                let description = command.describe();
+                (description.command, description.parameters)
+            })
+            .collect()
+    }
+
+    /// This function returns the message and the column of the error of the supplied operations
+    fn error(ops: &str) -> (String, usize) {
+        let mut parser = OpsParser {
+            chars: ops.chars().collect(),
+            position: 0,
+        };
+        let error = match parser.parse() {
+            Ok(parsed) => match parsed.iter().find_map(|op| op.to_command(3).err()) {
+                Some(error) => error,
+                None => panic!("expected an error in {}", ops),
+            },
+            Err(error) => error,
+        };
+        (error.message, error.column)
+    }
+
+    #[test]
+    fn quoted_values_keep_separators_and_whitespace() {
+        let commands = describe("text:br,20,15,'(c) A, B | C' | text:tl,1,2,\" x \"");
+        assert_eq!(commands[0].1["text"], json!("(c) A, B | C"));
+        assert_eq!(commands[1].1["text"], json!(" x "));
+    }
+
+    #[test]
+    fn shorthands_create_the_commands_of_the_registry() {
+        let commands = describe(
+            "crop:ratio=4:3 | resize:bbox=600x450,filter=lanczos3 | rotate:180 | flip:v | huerotate:-90",
+        );
+        let names: Vec<&str> = commands.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            ["crop", "resize_filter", "rotate", "flip", "huerotate"]
+        );
+        assert_eq!(commands[0].1, json!({ "x_ratio": 4.0, "y_ratio": 3.0 }));
+        assert_eq!(commands[1].1["filter"], json!("lanczos3"));
+    }
+
+    #[test]
+    fn operations_of_the_registry_take_named_and_default_values() {
+        let commands = describe("crop_box:10,10,200,100 | resize_l:nwidth=800 | blur:sigma=1.5");
+        assert_eq!(
+            commands[0].1,
+            json!({ "x": 10, "y": 10, "width": 200, "height": 100 })
+        );
+        assert_eq!(commands[1].0, "resize_filter");
+        assert_eq!(commands[2].1, json!({ "sigma": 1.5 }));
+    }
+
+    #[test]
+    fn commands_report_the_operation_which_creates_them_again() {
+        let ops =
+            "combine_br:resources/tests/test_small.png,5,5 | crop:1,2,30,40 | crop:ratio=16:9 \
+            | flip:h | flip:v | rotate:90 | rotate:270 | huerotate:45 | exif | blur:1.1 \
+            | resize:width=800 | resize:height=600 | resize:bbox=600x450,filter=gaussian \
+            | resize:exact=300x200 | text:tl,1,2,'(c) ACME' | invert | unsharpen:0.5,3";
+        for command in create_cmd_list_ops(ops, 3).unwrap() {
+            let (name, values) = command.operation().unwrap();
+            let operation = find_operation(name).unwrap();
+            let recreated = operation.command(3, name, values).unwrap();
+            assert_eq!(
+                recreated.describe().parameters,
+                command.describe().parameters,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_operations_point_at_their_name() {
+        let (message, column) = error("blur:1.5 | rezise:width=600");
+        assert!(message.starts_with("unknown operation rezise, expected one of "));
+        assert_eq!(column, 12);
+    }
+
+    #[test]
+    fn errors_point_at_the_column_of_the_value() {
+        assert_eq!(error("blur:x"), (String::from("expected f32, got x"), 6));
+        assert_eq!(
+            error("resize:width=600 | flip:d"),
+            (String::from("expected h or v, got d"), 25)
+        );
+        assert_eq!(
+            error("text:br,1,2,'open"),
+            (String::from("the quote ' is not closed"), 13)
+        );
+        assert_eq!(
+            error("blur 1.5"),
+            (String::from("expected : or |, found 1"), 6)
+        );
+    }
+
+    #[test]
+    fn junk_after_a_quoted_value_is_reported_as_such() {
+        assert_eq!(
+            error("text:br,1,2,'a' b"),
+            (
+                String::from("expected , or | or end of input after the quoted value, found b"),
+                17
+            )
+        );
+    }
+
+    #[test]
+    fn columns_count_characters_of_non_ascii_input() {
+        assert_eq!(
+            error("text:tl,1,2,'Grüße' ü"),
+            (
+                String::from("expected , or | or end of input after the quoted value, found ü"),
+                21
+            )
+        );
+        assert_eq!(
+            error("text:tl,1,2,'Grüße' | blür:1"),
+            (String::from("expected : or |, found ü"), 25)
+        );
+        assert_eq!(
+            error("text:tl,1,2,'Grüße' | blur:ö"),
+            (String::from("expected f32, got ö"), 28)
+        );
+    }
+}
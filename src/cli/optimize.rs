@@ -0,0 +1,360 @@
+use std::mem;
+
+use thumbnailer::{Orientation, Rotation};
+
+use crate::cli::Commands;
+use crate::commands::{CmdFlip, CmdRotate, Command};
+
+/// The commands, which change each pixel on its own and keep the dimensions, so a crop can be applied before them
+const PER_PIXEL: [&str; 4] = ["brighten", "contrast", "huerotate", "invert"];
+
+/// Representation of the combined orientation of consecutive rotate- and flip-commands as a struct
+///
+/// Every combination of rotations and flips equals a horizontal flip (or none), followed by a clockwise rotation.
+#[derive(Clone, Copy, Default)]
+struct Transform {
+    /// Contains whether the image is flipped horizontally first
+    flip: bool,
+    /// Contains the number of clockwise quarter turns after the flip, from 0 to 3
+    quarters: u8,
+}
+
+impl Transform {
+    /// This function applies the supplied rotate- or flip-command after the transform
+    ///
+    /// A horizontal flip after a rotation equals the inverse rotation after the flip,
+    /// and a vertical flip equals a horizontal flip followed by a rotation of 180°.
+    ///
+    /// Returns `true`, if the command is a rotate- or flip-command and was applied
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - The `Transform` struct
+    /// * `command` - The command to apply
+    fn then(&mut self, command: &dyn Command) -> bool {
+        let description = command.describe();
+        let parameters = &description.parameters;
+        match (description.command, parameters["rotation"].as_str()) {
+            ("rotate", Some("rotate90")) => self.quarters = (self.quarters + 1) % 4,
+            ("rotate", Some("rotate180")) => self.quarters = (self.quarters + 2) % 4,
+            ("rotate", Some("rotate270")) => self.quarters = (self.quarters + 3) % 4,
+            ("flip", _) => {
+                let quarters = match parameters["orientation"].as_str() {
+                    Some("vertical") => 2,
+                    _ => 0,
+                };
+                self.flip = !self.flip;
+                self.quarters = (quarters + 4 - self.quarters) % 4;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// This function converts the transform into the fewest rotate- and flip-commands
+    ///
+    /// Returns a new `Vec<Box<dyn Command>>` list, which is empty if the transform keeps the orientation
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Transform` struct
+    /// * `index` - The `index` as u32 of the first folded command
+    fn commands(&self, index: u32) -> Vec<Box<dyn Command>> {
+        let mut commands: Vec<Box<dyn Command>> = vec![];
+        match (self.flip, self.quarters) {
+            (true, 2) => {
+                commands.push(Box::new(CmdFlip::new(index, Orientation::Vertical)));
+                return commands;
+            }
+            (true, _) => commands.push(Box::new(CmdFlip::new(index, Orientation::Horizontal))),
+            (false, _) => {}
+        }
+        match self.quarters {
+            1 => commands.push(Box::new(CmdRotate::new(index, Rotation::Rotate90))),
+            2 => commands.push(Box::new(CmdRotate::new(index, Rotation::Rotate180))),
+            3 => commands.push(Box::new(CmdRotate::new(index, Rotation::Rotate270))),
+            _ => {}
+        }
+        commands
+    }
+}
+
+/// This function removes redundant work from the commands of the image and of each rendition (`--optimize`)
+///
+/// The result of the commands does not change:
+/// * consecutive rotate- and flip-commands are folded into a single flip and rotation, which are dropped if they cancel out
+/// * commands without effect (brighten 0, huerotate 0) and two consecutive invert-commands are dropped
+/// * crop-commands are moved before the directly preceding per-pixel filters (brighten, contrast, huerotate and invert),
+///   which then process only the cropped image. Filters, which read the neighbouring pixels (blur and unsharpen), are not passed.
+///
+/// Consecutive brighten-commands are kept, as the brightness is clipped after each of them.
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct with the sorted commands
+///
+/// # Examples
+/// ```
+/// # use thumbnailer::{Orientation, Rotation};
+/// # use thumbnailer_cli::cli::optimize::optimize;
+/// # use thumbnailer_cli::commands::{CmdFlip, CmdInvert, CmdRotate};
+/// # use thumbnailer_cli::Commands;
+/// let mut cmd_list = Commands {
+///     commands: vec![
+///         Box::new(CmdRotate::new(1, Rotation::Rotate180)),
+///         Box::new(CmdFlip::new(2, Orientation::Vertical)),
+///         Box::new(CmdInvert::new(3)),
+///         Box::new(CmdInvert::new(4)),
+///     ],
+///     ..Commands::default()
+/// };
+/// optimize(&mut cmd_list);
+/// assert_eq!(cmd_list.commands.len(), 1); // flip horizontal, at index 1
+/// ```
+pub fn optimize(cmd_list: &mut Commands) {
+    cmd_list.commands = optimize_commands(mem::take(&mut cmd_list.commands));
+    for rendition in &mut cmd_list.renditions {
+        rendition.commands = optimize_commands(mem::take(&mut rendition.commands));
+    }
+}
+
+/// This function removes redundant work from the supplied list of commands, as described by `optimize`
+///
+/// Returns the new `Vec<Box<dyn Command>>` list in the order of execution
+///
+/// # Arguments
+///
+/// * `commands` - The commands in the order of execution
+fn optimize_commands(commands: Vec<Box<dyn Command>>) -> Vec<Box<dyn Command>> {
+    let mut optimized: Vec<Box<dyn Command>> = vec![];
+    let mut folded: Option<(u32, Transform)> = None;
+
+    for command in commands {
+        let mut transform = folded.map_or(Transform::default(), |(_, transform)| transform);
+        if transform.then(command.as_ref()) {
+            let index = folded.map_or(command.get_index(), |(index, _)| index);
+            folded = Some((index, transform));
+            continue;
+        }
+        if let Some((index, transform)) = folded.take() {
+            optimized.append(&mut transform.commands(index));
+        }
+
+        let description = command.describe();
+        let parameters = &description.parameters;
+        match description.command {
+            "brighten" if parameters["value"] == 0 => {}
+            "huerotate" if parameters["degree"] == 0 => {}
+            "invert"
+                if optimized
+                    .last()
+                    .is_some_and(|last| is_command(last.as_ref(), "invert")) =>
+            {
+                optimized.pop();
+            }
+            "crop" => {
+                let position = optimized
+                    .iter()
+                    .rposition(|previous| {
+                        !PER_PIXEL
+                            .iter()
+                            .any(|name| is_command(previous.as_ref(), name))
+                    })
+                    .map_or(0, |position| position + 1);
+                optimized.insert(position, command);
+            }
+            _ => optimized.push(command),
+        }
+    }
+    if let Some((index, transform)) = folded {
+        optimized.append(&mut transform.commands(index));
+    }
+    optimized
+}
+
+/// This function checks, whether the supplied command is of the supplied type, such as `invert`
+///
+/// # Arguments
+///
+/// * `command` - The command
+/// * `name` - The name of the command, as given by `Command::describe`
+fn is_command(command: &dyn Command, name: &str) -> bool {
+    command.describe().command == name
+}
+
+#[cfg(test)]
+mod tests {
+    use thumbnailer::Crop;
+
+    use super::*;
+    use crate::commands::{CmdBlur, CmdBrighten, CmdContrast, CmdCrop, CmdInvert};
+
+    /// The pixels of an image with 3 columns and 2 rows, each with its own value
+    type Grid = Vec<Vec<u8>>;
+
+    /// This function returns the grid, which is transformed by the tests
+    fn grid() -> Grid {
+        vec![vec![1, 2, 3], vec![4, 5, 6]]
+    }
+
+    /// This function applies the supplied rotate- or flip-command to the supplied grid
+    fn apply(grid: Grid, command: &dyn Command) -> Grid {
+        let description = command.describe();
+        let parameters = &description.parameters;
+        let rotate90 = |grid: Grid| -> Grid {
+            (0..grid[0].len())
+                .map(|column| grid.iter().rev().map(|row| row[column]).collect())
+                .collect()
+        };
+        match (
+            parameters["rotation"].as_str(),
+            parameters["orientation"].as_str(),
+        ) {
+            (Some("rotate90"), _) => rotate90(grid),
+            (Some("rotate180"), _) => rotate90(rotate90(grid)),
+            (Some("rotate270"), _) => rotate90(rotate90(rotate90(grid))),
+            (_, Some("horizontal")) => grid
+                .into_iter()
+                .map(|row| row.into_iter().rev().collect())
+                .collect(),
+            (_, Some("vertical")) => grid.into_iter().rev().collect(),
+            _ => panic!("not a rotate- or flip-command: {}", description.command),
+        }
+    }
+
+    /// This function applies the supplied commands to the grid
+    fn apply_all(commands: &[Box<dyn Command>]) -> Grid {
+        commands
+            .iter()
+            .fold(grid(), |grid, command| apply(grid, command.as_ref()))
+    }
+
+    /// This function returns all rotate- and flip-commands
+    fn transforms() -> Vec<Box<dyn Command>> {
+        vec![
+            Box::new(CmdRotate::new(1, Rotation::Rotate90)),
+            Box::new(CmdRotate::new(1, Rotation::Rotate180)),
+            Box::new(CmdRotate::new(1, Rotation::Rotate270)),
+            Box::new(CmdFlip::new(1, Orientation::Horizontal)),
+            Box::new(CmdFlip::new(1, Orientation::Vertical)),
+        ]
+    }
+
+    /// This function returns the `Transform` structs of all 8 orientations
+    fn dihedral() -> Vec<Transform> {
+        [false, true]
+            .iter()
+            .flat_map(|&flip| (0..4).map(move |quarters| Transform { flip, quarters }))
+            .collect()
+    }
+
+    /// This function returns the names of the supplied commands
+    fn names(commands: &[Box<dyn Command>]) -> Vec<&'static str> {
+        commands
+            .iter()
+            .map(|command| command.describe().command)
+            .collect()
+    }
+
+    #[test]
+    fn transform_commands_give_the_orientation_of_the_transform() {
+        let lengths = [0, 1, 1, 1, 1, 2, 1, 2];
+        let mut grids = vec![];
+        for (transform, length) in dihedral().into_iter().zip(lengths) {
+            let commands = transform.commands(1);
+            let mut expected = grid();
+            if transform.flip {
+                expected = apply(expected, &CmdFlip::new(1, Orientation::Horizontal));
+            }
+            for _ in 0..transform.quarters {
+                expected = apply(expected, &CmdRotate::new(1, Rotation::Rotate90));
+            }
+            assert_eq!(apply_all(&commands), expected);
+            assert_eq!(commands.len(), length);
+            grids.push(expected);
+        }
+        grids.sort();
+        grids.dedup();
+        assert_eq!(grids.len(), 8);
+    }
+
+    #[test]
+    fn transform_then_applies_each_rotation_and_flip() {
+        for transform in dihedral() {
+            for command in transforms() {
+                let mut combined = transform;
+                assert!(combined.then(command.as_ref()));
+                let expected = apply(apply_all(&transform.commands(1)), command.as_ref());
+                assert_eq!(apply_all(&combined.commands(1)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn transform_then_ignores_other_commands() {
+        let mut transform = Transform::default();
+        assert!(!transform.then(&CmdInvert::new(1)));
+        assert!(transform.commands(1).is_empty());
+    }
+
+    #[test]
+    fn rotations_and_flips_which_cancel_out_are_dropped() {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(CmdRotate::new(1, Rotation::Rotate90)),
+            Box::new(CmdFlip::new(2, Orientation::Horizontal)),
+            Box::new(CmdRotate::new(3, Rotation::Rotate90)),
+            Box::new(CmdFlip::new(4, Orientation::Horizontal)),
+        ];
+        assert_eq!(apply_all(&commands), grid());
+        assert!(optimize_commands(commands).is_empty());
+    }
+
+    #[test]
+    fn an_invert_pair_is_dropped() {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(CmdInvert::new(1)),
+            Box::new(CmdInvert::new(2)),
+            Box::new(CmdInvert::new(3)),
+        ];
+        let optimized = optimize_commands(commands);
+        assert_eq!(names(&optimized), ["invert"]);
+        assert_eq!(optimized[0].get_index(), 3);
+    }
+
+    #[test]
+    fn inverts_with_a_command_in_between_are_kept() {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(CmdInvert::new(1)),
+            Box::new(CmdBlur::new(2, 1.0)),
+            Box::new(CmdInvert::new(3)),
+        ];
+        assert_eq!(
+            names(&optimize_commands(commands)),
+            ["invert", "blur", "invert"]
+        );
+    }
+
+    #[test]
+    fn a_crop_moves_before_per_pixel_commands_only() {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(CmdBrighten::new(1, 10)),
+            Box::new(CmdBlur::new(2, 1.0)),
+            Box::new(CmdContrast::new(3, 5.0)),
+            Box::new(CmdInvert::new(4)),
+            Box::new(CmdCrop::new(5, Crop::Box(0, 0, 10, 10))),
+        ];
+        assert_eq!(
+            names(&optimize_commands(commands)),
+            ["brighten", "blur", "crop", "contrast", "invert"]
+        );
+    }
+
+    #[test]
+    fn a_crop_at_the_start_stays_there() {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(CmdInvert::new(1)),
+            Box::new(CmdCrop::new(2, Crop::Ratio(4.0, 3.0))),
+        ];
+        assert_eq!(names(&optimize_commands(commands)), ["crop", "invert"]);
+    }
+}
@@ -0,0 +1,573 @@
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::cli::{Commands, Rendition, ARG_EMIT_PIPELINE, ARG_PIPELINE};
+use crate::commands::registry::{find_operation, operation_names};
+use crate::commands::Command;
+use crate::error::{Error, Result};
+use crate::format::{png_compression_name, png_filter_name, Encoding};
+
+/// Representation of a pipeline file as a struct
+///
+/// The entries of both formats (TOML or JSON) are read as JSON values and converted by the operations of the registry.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Pipeline {
+    /// Contains the entries of the pipeline in the order of execution
+    #[serde(default)]
+    step: Vec<serde_json::Value>,
+    /// Contains the named outputs, which share the entries of `step` as common prefix
+    #[serde(default)]
+    output: Vec<Output>,
+    /// Contains the output format, overriding the detection by the extension of the output path
+    format: Option<String>,
+    /// Contains the JPEG quality from 1 to 100
+    quality: Option<u8>,
+    /// Contains the name of the PNG compression level
+    png_compression: Option<String>,
+    /// Contains the name of the PNG filter type
+    png_filter: Option<String>,
+    /// Contains the maximum file size in bytes
+    max_bytes: Option<u64>,
+}
+
+/// Representation of a named output of a pipeline file as a struct
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Output {
+    /// Contains the name of the output
+    name: String,
+    /// Contains the output path or output template of the output
+    path: String,
+    /// Contains the output format, overriding the detection by the extension of `path`
+    format: Option<String>,
+    /// Contains the JPEG quality from 1 to 100
+    quality: Option<u8>,
+    /// Contains the name of the PNG compression level
+    png_compression: Option<String>,
+    /// Contains the name of the PNG filter type
+    png_filter: Option<String>,
+    /// Contains the maximum file size in bytes
+    max_bytes: Option<u64>,
+    /// Contains the entries, which are only applied to this output, in the order of execution
+    #[serde(default)]
+    step: Vec<serde_json::Value>,
+}
+
+/// This function is reading the given pipeline file
+///
+/// Files with the extension `json` are read as JSON, files with the extension `toml` as TOML.
+/// Each entry of the `step` list names the operation by `op` and supplies its parameters by name.
+/// Each entry of the optional `output` list declares a named rendition with its output path and its own trailing `step` list.
+/// The image is loaded once, the common steps are applied once and only the trailing steps are applied per rendition.
+/// The optional keys `format`, `quality`, `png_compression`, `png_filter` and `max_bytes` set the encoder settings of all outputs or of a single output:
+/// ```toml
+/// [[step]]
+/// op = "crop_ratio"
+/// x_ratio = 4.0
+/// y_ratio = 3.0
+///
+/// [[output]]
+/// name = "320w"
+/// path = "{stem}_320w.jpg"
+/// quality = 80
+///
+/// [[output.step]]
+/// op = "resize"
+/// nwidth = 320
+///
+/// [[output]]
+/// name = "640w"
+/// path = "{stem}_640w.png"
+///
+/// [[output.step]]
+/// op = "resize"
+/// nwidth = 640
+/// ```
+///
+/// Returns a new `Commands` struct with the commands in the order of the entries, or an `Error` pointing at the first entry which could not be parsed
+///
+/// # Arguments
+///
+/// * `path` - The path of the pipeline file
+/// * `index` - The `index` as u32 of arguments list, at which the pipeline was supplied
+pub fn create_cmd_list_pipeline(path: &str, index: u32) -> Result<Commands> {
+    let content = fs::read_to_string(path).map_err(|source| Error::Io {
+        arg: String::from(ARG_PIPELINE),
+        path: String::from(path),
+        source,
+    })?;
+    let file_error =
+        |e: &dyn fmt::Display| Error::argument(ARG_PIPELINE, format!("{}: {}", path, e));
+
+    match Path::new(path).extension().and_then(OsStr::to_str) {
+        Some("json") => {
+            let pipeline: Pipeline = serde_json::from_str(&content).map_err(|e| file_error(&e))?;
+            create_cmd_list(pipeline, &format!("{} {}", ARG_PIPELINE, path), index)
+        }
+        Some("toml") => {
+            let pipeline: Pipeline = toml::from_str(&content).map_err(|e| file_error(&e))?;
+            create_cmd_list(pipeline, &format!("{} {}", ARG_PIPELINE, path), index)
+        }
+        extension => Err(Error::UnsupportedFormat {
+            arg: String::from(ARG_PIPELINE),
+            format: String::from(extension.unwrap_or(path)),
+        }),
+    }
+}
+
+/// This function converts a TOML table with the keys of a pipeline file, such as a preset, into a `Commands` struct
+///
+/// Returns a new `Commands` struct, or an `Error` pointing at the first entry which could not be parsed
+///
+/// # Arguments
+///
+/// * `table` - The TOML table, containing the keys of a pipeline file
+/// * `file` - The description of the table to report errors, such as `preset full_hd`
+/// * `index` - The `index` as u32 of arguments list, at which the table was supplied
+pub(crate) fn create_cmd_list_table(
+    table: toml::Value,
+    file: &str,
+    index: u32,
+) -> Result<Commands> {
+    let pipeline: Pipeline = table
+        .try_into()
+        .map_err(|e| Error::argument(file, e.to_string()))?;
+    create_cmd_list(pipeline, file, index)
+}
+
+/// This function converts the entries and the outputs of a pipeline file into a `Commands` struct
+///
+/// Returns a new `Commands` struct, or an `Error` pointing at the first entry which could not be parsed
+///
+/// # Arguments
+///
+/// * `pipeline` - The `Pipeline` struct read from the file
+/// * `file` - The description of the pipeline file to report errors, such as `pipeline steps.toml`
+/// * `index` - The `index` as u32 of arguments list, at which the pipeline was supplied
+fn create_cmd_list(pipeline: Pipeline, file: &str, index: u32) -> Result<Commands> {
+    let mut cmd_list = Commands {
+        commands: create_cmd_list_steps(pipeline.step, file, index)?,
+        renditions: vec![],
+        encoding: Encoding::new(
+            file,
+            pipeline.format,
+            pipeline.quality,
+            pipeline.png_compression.as_deref(),
+            pipeline.png_filter.as_deref(),
+            pipeline.max_bytes,
+        )?,
+    };
+
+    for output in pipeline.output {
+        let entry = format!("{}, output {}", file, output.name);
+        if output.name.is_empty() || output.path.is_empty() {
+            return Err(Error::argument(
+                file,
+                String::from("each output requires a name and a path"),
+            ));
+        }
+        if cmd_list
+            .renditions
+            .iter()
+            .any(|rendition| rendition.name == output.name)
+        {
+            return Err(Error::argument(
+                &entry,
+                String::from("the name is not unique"),
+            ));
+        }
+
+        cmd_list.renditions.push(Rendition {
+            commands: create_cmd_list_steps(output.step, &entry, index)?,
+            name: output.name,
+            output: output.path,
+            encoding: Encoding::new(
+                &entry,
+                output.format,
+                output.quality,
+                output.png_compression.as_deref(),
+                output.png_filter.as_deref(),
+                output.max_bytes,
+            )?,
+        });
+    }
+
+    Ok(cmd_list)
+}
+
+/// This function converts the supplied entries of a pipeline file into commands
+///
+/// Returns a new `Vec<Box<dyn Command>>` list in the order of the entries, or an `Error` pointing at the first entry which could not be parsed
+///
+/// # Arguments
+///
+/// * `steps` - The entries of a `step` list
+/// * `file` - The description of the pipeline file (or one of its outputs) to report errors
+/// * `index` - The `index` as u32 of arguments list, at which the pipeline was supplied
+fn create_cmd_list_steps(
+    steps: Vec<serde_json::Value>,
+    file: &str,
+    index: u32,
+) -> Result<Vec<Box<dyn Command>>> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let entry = format!(
+                "{}, step {} ({})",
+                file,
+                i + 1,
+                step.get("op")
+                    .and_then(|op| op.as_str())
+                    .unwrap_or("missing op")
+            );
+            create_cmd_step(step, index, &entry)
+        })
+        .collect()
+}
+
+/// This function converts a single entry of a pipeline file into the command of its operation
+///
+/// The value of `op` is the name of the operation, which is the name of the matching command line argument, all other keys are the named parameters of it.
+/// Parameters with a default value, such as `nheight` of `resize`, may be omitted.
+///
+/// Returns a new `Box<dyn Command>`, or an `Error` pointing at the entry if the operation, a key or a value is invalid
+///
+/// # Arguments
+///
+/// * `step` - The entry of a `step` list
+/// * `index` - The `index` as u32 of arguments list, at which the pipeline was supplied
+/// * `entry` - The description of the pipeline entry to report errors
+///
+/// # Examples
+/// The example is not compiled, as `create_cmd_step` is private.
+///
+/// ```ignore
+/// let command = create_cmd_step(&json!({ "op": "blur", "sigma": 2.5 }), 3, "step 1 (blur)").unwrap();
+/// assert_eq!(command.describe().command, "blur");
+/// assert!(create_cmd_step(&json!({ "op": "blur", "sigma": "2.5" }), 3, "step 1 (blur)").is_err());
+/// ```
+fn create_cmd_step(step: &serde_json::Value, index: u32, entry: &str) -> Result<Box<dyn Command>> {
+    let error = |message: String| Error::argument(entry, message);
+    let keys = step
+        .as_object()
+        .ok_or_else(|| error(String::from("expected a table with the key op")))?;
+    let name = match keys.get("op") {
+        Some(serde_json::Value::String(name)) => name,
+        Some(_) => return Err(error(String::from("op expects the name of an operation"))),
+        None => return Err(error(String::from("missing key op"))),
+    };
+    let operation = find_operation(name).ok_or_else(|| {
+        error(format!(
+            "unknown op {}, expected one of {}",
+            name,
+            operation_names().join(", ")
+        ))
+    })?;
+
+    let names: Vec<&str> = operation
+        .parameters
+        .iter()
+        .map(|parameter| parameter.name)
+        .collect();
+    if let Some(key) = keys
+        .keys()
+        .find(|key| *key != "op" && !names.contains(&key.as_str()))
+    {
+        return Err(error(match names.is_empty() {
+            true => format!("unknown key {}, {} expects no values", key, name),
+            false => format!("unknown key {}, expected one of {}", key, names.join(", ")),
+        }));
+    }
+
+    let values = operation
+        .parameters
+        .iter()
+        .map(|parameter| match keys.get(parameter.name) {
+            Some(value) => parameter.kind.text(value).ok_or_else(|| {
+                error(format!(
+                    "{} expects {}, got {}",
+                    parameter.name,
+                    parameter.kind.name(),
+                    value
+                ))
+            }),
+            None => parameter
+                .default
+                .map(String::from)
+                .ok_or_else(|| error(format!("missing key {}", parameter.name))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    operation.command(index, entry, values)
+}
+
+/// This function writes the supplied commands, renditions and encoder settings as pipeline file
+///
+/// Files with the extension `json` are written as JSON, files with the extension `toml` as TOML.
+/// Running the pipeline file applies the same commands in the same order, so it reproduces the same output.
+///
+/// Returns `()`, or an `Error` if a command can not be expressed in a pipeline file or the file could not be written
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct with the sorted commands, as used to process the images
+/// * `path` - The path of the pipeline file to write
+pub fn emit_pipeline(cmd_list: &Commands, path: &str) -> Result<()> {
+    let mut pipeline = emit_entries(&cmd_list.commands, &cmd_list.encoding)?;
+    if !cmd_list.renditions.is_empty() {
+        let outputs = cmd_list
+            .renditions
+            .iter()
+            .map(|rendition| {
+                let mut output = emit_entries(&rendition.commands, &rendition.encoding)?;
+                output.insert(String::from("name"), json!(rendition.name));
+                output.insert(String::from("path"), json!(rendition.output));
+                Ok(serde_json::Value::Object(output))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        pipeline.insert(String::from("output"), serde_json::Value::Array(outputs));
+    }
+    let pipeline = serde_json::Value::Object(pipeline);
+
+    let content = match Path::new(path).extension().and_then(OsStr::to_str) {
+        Some("json") => serde_json::to_string_pretty(&pipeline).map_err(|e| e.to_string()),
+        // The TOML value emits the plain keys before the tables, as required by TOML
+        Some("toml") => toml::Value::try_from(&pipeline)
+            .and_then(|pipeline| toml::to_string_pretty(&pipeline))
+            .map_err(|e| e.to_string()),
+        extension => {
+            return Err(Error::UnsupportedFormat {
+                arg: String::from(ARG_EMIT_PIPELINE),
+                format: String::from(extension.unwrap_or(path)),
+            })
+        }
+    }
+    .map_err(|e| Error::argument(ARG_EMIT_PIPELINE, format!("{}: {}", path, e)))?;
+
+    fs::write(path, content).map_err(|source| Error::Io {
+        arg: String::from(ARG_EMIT_PIPELINE),
+        path: String::from(path),
+        source,
+    })
+}
+
+/// This function converts the supplied commands and encoder settings into the keys of a pipeline file (or one of its outputs)
+///
+/// Returns a new map with the `step` list and the encoder settings, which are set, or an `Error::Argument` if a command can not be expressed
+///
+/// # Arguments
+///
+/// * `commands` - The implementors of `Command` in the order of execution
+/// * `encoding` - The `Encoding` struct with the encoder settings
+fn emit_entries(
+    commands: &[Box<dyn Command>],
+    encoding: &Encoding,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut entries = serde_json::Map::new();
+    if let Some(format) = &encoding.format {
+        entries.insert(String::from("format"), json!(format));
+    }
+    if let Some(quality) = encoding.quality {
+        entries.insert(String::from("quality"), json!(quality));
+    }
+    if let Some(png_compression) = encoding.png_compression {
+        let name = png_compression_name(png_compression);
+        entries.insert(String::from("png_compression"), json!(name));
+    }
+    if let Some(png_filter) = encoding.png_filter {
+        entries.insert(
+            String::from("png_filter"),
+            json!(png_filter_name(png_filter)),
+        );
+    }
+    if let Some(max_bytes) = encoding.max_bytes {
+        entries.insert(String::from("max_bytes"), json!(max_bytes));
+    }
+
+    let steps = commands
+        .iter()
+        .map(|command| emit_step(command.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.insert(String::from("step"), serde_json::Value::Array(steps));
+    Ok(entries)
+}
+
+/// This function converts a command into an entry of the operation of the registry, which creates the same command
+///
+/// Values, which equal the default of their parameter, are omitted.
+///
+/// Returns the entry with the key `op` and the named parameters, or an `Error::Argument` if the command can not be expressed
+///
+/// # Arguments
+///
+/// * `command` - The command
+///
+/// # Examples
+/// The example is not compiled, as `emit_step` is private.
+///
+/// ```ignore
+/// let step = emit_step(&CmdBlur::new(3, 2.5)).unwrap();
+/// assert_eq!(step, json!({ "op": "blur", "sigma": 2.5 }));
+/// ```
+fn emit_step(command: &dyn Command) -> Result<serde_json::Value> {
+    let error = || {
+        let description = command.describe();
+        Error::argument(
+            ARG_EMIT_PIPELINE,
+            format!(
+                "the command {:02}. {} can not be written to a pipeline file",
+                description.index, description.command
+            ),
+        )
+    };
+    let (name, values) = command.operation().ok_or_else(error)?;
+    let operation = find_operation(name).ok_or_else(error)?;
+
+    let mut step = serde_json::Map::new();
+    step.insert(String::from("op"), json!(operation.name));
+    for (parameter, value) in operation.parameters.iter().zip(values) {
+        if parameter.default == Some(value.as_str()) {
+            continue;
+        }
+        let value = parameter.kind.value(&value).ok_or_else(error)?;
+        step.insert(String::from(parameter.name), value);
+    }
+    Ok(serde_json::Value::Object(step))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    /// This function converts the supplied TOML content of a pipeline file into a `Commands` struct
+    fn read(content: &str) -> Result<Commands> {
+        let table: toml::Value = toml::from_str(content).unwrap();
+        create_cmd_list_table(table, "pipeline test.toml", 3)
+    }
+
+    /// This function returns the message of the error of the supplied TOML content of a pipeline file
+    fn error(content: &str) -> String {
+        match read(content) {
+            Err(Error::Argument { message, .. }) => message,
+            _ => panic!("expected an error reading {}", content),
+        }
+    }
+
+    #[test]
+    fn pipeline_files_create_the_commands_renditions_and_encoding() {
+        let cmd_list = read(
+            r#"
+            quality = 80
+            step = [{ op = "crop_ratio", x_ratio = 4.0, y_ratio = 3.0 }, { op = "blur", sigma = 1.5 }]
+
+            [[output]]
+            name = "small"
+            path = "{stem}_small.png"
+            png_compression = "best"
+            step = [{ op = "resize", nwidth = 320 }]
+            "#,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = cmd_list
+            .commands
+            .iter()
+            .map(|command| command.describe().command)
+            .collect();
+        assert_eq!(names, ["crop", "blur"]);
+        assert_eq!(cmd_list.encoding.quality, Some(80));
+        assert_eq!(cmd_list.renditions.len(), 1);
+        assert_eq!(cmd_list.renditions[0].name, "small");
+        assert_eq!(cmd_list.renditions[0].output, "{stem}_small.png");
+        assert_eq!(cmd_list.renditions[0].commands.len(), 1);
+        assert!(cmd_list.renditions[0].encoding.png_compression.is_some());
+    }
+
+    #[test]
+    fn unknown_keys_of_a_step_are_rejected() {
+        let message = error(r#"step = [{ op = "blur", sigma = 1.5, radius = 2 }]"#);
+        assert_eq!(message, "unknown key radius, expected one of sigma");
+    }
+
+    #[test]
+    fn unknown_top_level_keys_are_rejected() {
+        let message = error(r#"steps = [{ op = "blur", sigma = 1.5 }]"#);
+        assert!(message.contains("unknown field `steps`"), "{}", message);
+    }
+
+    #[test]
+    fn values_of_the_wrong_type_are_rejected() {
+        let message = error(r#"step = [{ op = "blur", sigma = "strong" }]"#);
+        assert!(message.starts_with("sigma expects"), "{}", message);
+        let message = error(r#"quality = "high""#);
+        assert!(message.contains("invalid type"), "{}", message);
+    }
+
+    #[test]
+    fn emitted_pipelines_are_read_back_alike() {
+        let cmd_list = read(
+            r#"
+            quality = 80
+            max_bytes = 40000
+            step = [{ op = "rotate90" }, { op = "text_br", text = "(c) ACME", x_offset = 5, y_offset = 5 }]
+
+            [[output]]
+            name = "small"
+            path = "{stem}_small.png"
+            png_compression = "best"
+            png_filter = "paeth"
+            step = [{ op = "resize", nwidth = 320 }, { op = "blur", sigma = 1.1 }]
+
+            [[output]]
+            name = "large"
+            path = "large/{stem}.jpg"
+            "#,
+        )
+        .unwrap();
+
+        let dir = env::temp_dir().join(format!("thumbnailer_pipeline_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for extension in &["toml", "json"] {
+            let path = dir.join(format!("pipeline.{}", extension));
+            let path = path.to_str().unwrap();
+            emit_pipeline(&cmd_list, path).unwrap();
+            let read_back = create_cmd_list_pipeline(path, 3).unwrap();
+
+            let again = dir.join(format!("again.{}", extension));
+            let again = again.to_str().unwrap();
+            emit_pipeline(&read_back, again).unwrap();
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                fs::read_to_string(again).unwrap()
+            );
+
+            assert_eq!(read_back.encoding.quality, Some(80));
+            assert_eq!(read_back.encoding.max_bytes, Some(40000));
+            let outputs: Vec<(&str, &str, usize)> = read_back
+                .renditions
+                .iter()
+                .map(|rendition| {
+                    let name = rendition.name.as_str();
+                    (name, rendition.output.as_str(), rendition.commands.len())
+                })
+                .collect();
+            assert_eq!(
+                outputs,
+                [
+                    ("small", "{stem}_small.png", 2),
+                    ("large", "large/{stem}.jpg", 0)
+                ]
+            );
+            assert!(read_back.renditions[0].encoding.png_filter.is_some());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
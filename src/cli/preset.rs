@@ -0,0 +1,887 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::cli::pipeline::create_cmd_list_table;
+use crate::cli::{Commands, ARG_PRESET};
+use crate::commands::registry::Kind;
+use crate::commands::Command;
+use crate::error::{Error, Result};
+
+/// The presets, which are shipped with `thumbnailer_cli`, in the format of a preset file
+const BUILT_IN_PRESETS: &str = include_str!("../../resources/presets.toml");
+
+/// Name of the project-local preset file, which is read from the current directory
+pub const PRESET_FILE: &str = "thumbnailer_presets.toml";
+
+/// Path of the per-user preset file, relative to the configuration directory of the user
+const USER_PRESET_FILE: &str = "thumbnailer/presets.toml";
+
+/// Representation of the origin of a preset as an enum
+#[derive(Clone)]
+pub enum Source {
+    /// The preset is shipped with `thumbnailer_cli`
+    BuiltIn,
+    /// The preset is defined in the preset file at the given path
+    File(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in"),
+            Source::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Representation of a declared parameter of a preset as a struct
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Parameter {
+    /// Contains the type of the parameter
+    #[serde(rename = "type")]
+    pub kind: Kind,
+    /// Contains the value, which is used if the parameter is not supplied, or `None` if it is required
+    pub default: Option<toml::Value>,
+    /// Contains the optional description of the parameter
+    pub description: Option<String>,
+}
+
+/// Representation of a named preset as a struct
+pub struct Preset {
+    /// Contains the name of the preset, as supplied to `--preset`
+    pub name: String,
+    /// Contains the optional description of the preset
+    pub description: Option<String>,
+    /// Contains the origin of the preset
+    pub source: Source,
+    /// Contains the declared parameters by name, which are substituted for their `{name}` placeholders
+    pub parameters: Vec<(String, Parameter)>,
+    /// Contains the TOML table of the preset, with the keys of a pipeline file
+    table: toml::Value,
+}
+
+impl Preset {
+    /// This function converts the preset into a `Commands` struct, after substituting the parameters and expanding the included presets
+    ///
+    /// A string of the preset, which consists of a single placeholder such as `"{width}"`, is replaced by the typed value of the parameter.
+    /// Placeholders within a longer string, such as `"(c) {owner}"`, are replaced by the value as text.
+    ///
+    /// All commands get the supplied `index`, so they keep their order within the preset and are placed at the position of `--preset`.
+    ///
+    /// Returns a new `Commands` struct, or an `Error` if a parameter is unknown, missing or mistyped, presets include each other or an entry could not be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Preset` struct
+    /// * `presets` - All available presets, which may be included
+    /// * `arguments` - The supplied parameters as `name=value`
+    /// * `index` - The `index` as u32 of arguments list, at which the preset was supplied
+    ///
+    /// # Examples
+    /// ```
+    /// # use thumbnailer_cli::cli::preset::{find_preset, load_presets};
+    /// let presets = load_presets().unwrap();
+    /// let preset = find_preset(&presets, "app_copyright").unwrap();
+    /// assert!(preset.commands(&presets, &["owner=ACME", "width=800"], 3).is_ok());
+    /// assert!(preset.commands(&presets, &["width=wide"], 3).is_err());
+    /// ```
+    pub fn commands(&self, presets: &[Preset], arguments: &[&str], index: u32) -> Result<Commands> {
+        let table = self.expand(presets, arguments, &mut vec![])?;
+        let file = format!("{} {} ({})", ARG_PRESET, self.name, self.source);
+        create_cmd_list_table(table, &file, index)
+    }
+
+    /// This function substitutes the parameters of the preset and replaces each entry `op = "preset"` by the entries of the named preset
+    ///
+    /// The other keys of such an entry are the parameters of the included preset, such as
+    /// `{ op = "preset", name = "watermark", owner = "{owner}" }`.
+    /// The outputs of all included presets are added after the outputs of the including preset.
+    /// The encoder settings of the including preset take precedence, followed by the ones of the last included preset,
+    /// just like the last `--preset` takes precedence on the command line.
+    ///
+    /// Returns the expanded TOML table, or an `Error::Argument` if a parameter is invalid or presets include each other
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Preset` struct
+    /// * `presets` - All available presets, which may be included
+    /// * `arguments` - The supplied parameters as `name=value`
+    /// * `including` - The names of the presets, which are currently expanded, to detect cycles
+    fn expand(
+        &self,
+        presets: &[Preset],
+        arguments: &[&str],
+        including: &mut Vec<String>,
+    ) -> Result<toml::Value> {
+        if including.contains(&self.name) {
+            including.push(self.name.clone());
+            return Err(Error::argument(
+                ARG_PRESET,
+                format!(
+                    "the preset {} includes itself: {}",
+                    self.name,
+                    including.join(" → ")
+                ),
+            ));
+        }
+        including.push(self.name.clone());
+
+        let values = self.parameter_values(arguments)?;
+        let mut table = match substitute(self.table.clone(), &values) {
+            toml::Value::Table(table) => table,
+            _ => unreachable!("a preset is always read from a table"),
+        };
+
+        let mut included = vec![];
+        if let Some(toml::Value::Array(steps)) = table.get_mut("step") {
+            let mut expanded = vec![];
+            for (i, step) in steps.drain(..).enumerate() {
+                if step.get("op").and_then(toml::Value::as_str) != Some("preset") {
+                    expanded.push(step);
+                    continue;
+                }
+
+                let entry = format!("{} {}, step {} (preset)", ARG_PRESET, self.name, i + 1);
+                let mut step = match step {
+                    toml::Value::Table(step) => step,
+                    _ => unreachable!("the key op is only found in tables"),
+                };
+                step.remove("op");
+                let name = match step.remove("name") {
+                    Some(toml::Value::String(name)) => name,
+                    _ => {
+                        return Err(Error::argument(
+                            &entry,
+                            String::from("the name of the included preset is missing"),
+                        ))
+                    }
+                };
+                // Strings are passed as text, other values as TOML, which the parameter types parse alike
+                let arguments: Vec<String> = step
+                    .into_iter()
+                    .map(|(key, value)| match value {
+                        toml::Value::String(value) => format!("{}={}", key, value),
+                        value => format!("{}={}", key, value),
+                    })
+                    .collect();
+                let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+                let mut preset =
+                    find_preset(presets, &name)?.expand(presets, &arguments, including)?;
+                if let Some(toml::Value::Array(steps)) = preset
+                    .as_table_mut()
+                    .and_then(|preset| preset.remove("step"))
+                {
+                    expanded.extend(steps);
+                }
+                included.push(preset);
+            }
+            *steps = expanded;
+        }
+
+        // Outputs of included presets are added in the order of their steps, other keys apply unless they are set already
+        let mut outputs = vec![];
+        let mut settings = toml::value::Table::new();
+        for preset in included {
+            let preset = match preset {
+                toml::Value::Table(preset) => preset,
+                _ => continue,
+            };
+            for (key, value) in preset {
+                match value {
+                    toml::Value::Array(mut added) if key == "output" => outputs.append(&mut added),
+                    value => {
+                        settings.insert(key, value);
+                    }
+                }
+            }
+        }
+        for (key, value) in settings {
+            table.entry(key).or_insert(value);
+        }
+        if !outputs.is_empty() {
+            match table
+                .entry("output")
+                .or_insert_with(|| toml::Value::Array(vec![]))
+            {
+                toml::Value::Array(known) => known.append(&mut outputs),
+                _ => {
+                    return Err(Error::argument(
+                        ARG_PRESET,
+                        format!("{}: the key output is no list of outputs", self.name),
+                    ))
+                }
+            }
+        }
+
+        including.pop();
+        Ok(toml::Value::Table(table))
+    }
+
+    /// This function resolves the values of all declared parameters from the supplied ones and the defaults
+    ///
+    /// Returns a new `Vec<(String, toml::Value)>` list, or an `Error::Argument` if a parameter is unknown, missing or mistyped
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Preset` struct
+    /// * `arguments` - The supplied parameters as `name=value`
+    fn parameter_values(&self, arguments: &[&str]) -> Result<Vec<(String, toml::Value)>> {
+        let error =
+            |message: String| Error::argument(ARG_PRESET, format!("{}: {}", self.name, message));
+
+        let mut supplied: Vec<(&str, &str)> = vec![];
+        for argument in arguments {
+            let (name, value) = match argument.split_once('=') {
+                Some((name, value)) if !name.is_empty() => (name, value),
+                _ => {
+                    return Err(error(format!(
+                        "expected a parameter as name=value, got {}",
+                        argument
+                    )))
+                }
+            };
+            if !self.parameters.iter().any(|(known, _)| known == name) {
+                return Err(error(format!(
+                    "unknown parameter {}, expected {}",
+                    name,
+                    self.parameter_names()
+                )));
+            }
+            if supplied.iter().any(|(known, _)| *known == name) {
+                return Err(error(format!("the parameter {} is supplied twice", name)));
+            }
+            supplied.push((name, value));
+        }
+
+        self.parameters
+            .iter()
+            .map(|(name, parameter)| {
+                let value = match supplied.iter().find(|(known, _)| known == name) {
+                    Some((_, value)) => typed_value(parameter.kind, value).ok_or_else(|| {
+                        error(format!(
+                            "the parameter {} expects a value of type {}, got {}",
+                            name,
+                            parameter.kind.name(),
+                            value
+                        ))
+                    })?,
+                    None => parameter.default.clone().ok_or_else(|| {
+                        error(format!(
+                            "the parameter {} of type {} is required",
+                            name,
+                            parameter.kind.name()
+                        ))
+                    })?,
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// This function returns the names of the declared parameters, to report errors
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Preset` struct
+    fn parameter_names(&self) -> String {
+        match self.parameters.is_empty() {
+            true => String::from("no parameters"),
+            false => {
+                let names: Vec<&str> = self
+                    .parameters
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                format!("one of {}", names.join(", "))
+            }
+        }
+    }
+}
+
+/// This function loads all available presets
+///
+/// The built-in presets are read first, followed by the per-user preset file
+/// (`$XDG_CONFIG_HOME/thumbnailer/presets.toml`, `~/.config/thumbnailer/presets.toml` or `%APPDATA%\thumbnailer\presets.toml`)
+/// and the project-local preset file `thumbnailer_presets.toml` in the current directory.
+/// A preset replaces a previously read preset of the same name, so a project can override the presets of the user, and both can override the built-in ones.
+/// Missing preset files are skipped.
+///
+/// Each top-level table of a preset file is a preset, named by its key, with the keys of a pipeline file, an optional `description`
+/// and the optional table `params`, which declares each parameter by name with its `type` (string, integer, unsigned, float or boolean),
+/// an optional `default` and an optional `description`.
+///
+/// Returns a new `Vec<Preset>` list in the order the names were first read (alphabetical within a file), or an `Error` if a preset file could not be read
+///
+/// # Examples
+/// ```
+/// # use thumbnailer_cli::cli::preset::load_presets;
+/// let presets = load_presets().unwrap();
+/// assert!(presets.iter().any(|preset| preset.name == "full_hd"));
+/// ```
+pub fn load_presets() -> Result<Vec<Preset>> {
+    let mut presets = vec![];
+    read_presets(BUILT_IN_PRESETS, Source::BuiltIn, &mut presets)?;
+
+    let files = user_preset_file()
+        .into_iter()
+        .chain(Some(PathBuf::from(PRESET_FILE)));
+    for path in files {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(source) => {
+                return Err(Error::Io {
+                    arg: String::from(ARG_PRESET),
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        read_presets(&content, Source::File(path), &mut presets)?;
+    }
+    Ok(presets)
+}
+
+/// This function converts the preset of the supplied name into a `Commands` struct
+///
+/// Returns a new `Commands` struct, or an `Error::Argument` if the preset is unknown or could not be parsed
+///
+/// # Arguments
+///
+/// * `name` - The name of the preset
+/// * `arguments` - The supplied parameters of the preset as `name=value`
+/// * `index` - The `index` as u32 of arguments list, at which the preset was supplied
+///
+/// # Examples
+/// ```
+/// # use thumbnailer_cli::cli::preset::create_cmd_list_preset;
+/// let preset = create_cmd_list_preset("app_copyright", &["owner=ACME"], 3).unwrap();
+/// assert_eq!(preset.commands.len(), 3);
+/// ```
+pub fn create_cmd_list_preset(name: &str, arguments: &[&str], index: u32) -> Result<Commands> {
+    let presets = load_presets()?;
+    find_preset(&presets, name)?.commands(&presets, arguments, index)
+}
+
+/// This function returns the preset of the supplied name
+///
+/// Returns the `Preset`, or an `Error::Argument` if the preset is unknown
+///
+/// # Arguments
+///
+/// * `presets` - All available presets
+/// * `name` - The name of the preset
+pub fn find_preset<'a>(presets: &'a [Preset], name: &str) -> Result<&'a Preset> {
+    match presets.iter().find(|preset| preset.name == name) {
+        Some(preset) => Ok(preset),
+        None => Err(Error::argument(
+            ARG_PRESET,
+            format!(
+                "unknown preset {}, expected one of {} (see --list-presets)",
+                name,
+                presets
+                    .iter()
+                    .map(|preset| preset.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )),
+    }
+}
+
+/// This function prints all available presets with their origin, description, parameters and the commands they expand to
+///
+/// The expansion uses the defaults of the parameters. Presets, which could not be expanded, are listed with their error.
+///
+/// Returns `()`, or an `Error` if a preset file could not be read
+pub fn list_presets() -> Result<()> {
+    let presets = load_presets()?;
+    for preset in &presets {
+        println!("{} ({})", preset.name, preset.source);
+        if let Some(description) = &preset.description {
+            println!("    {}", description);
+        }
+        for (name, parameter) in &preset.parameters {
+            let default = match &parameter.default {
+                Some(default) => format!(" = {}", default),
+                None => String::from(" (required)"),
+            };
+            match &parameter.description {
+                Some(description) => println!(
+                    "    param {}: {}{}\t{}",
+                    name,
+                    parameter.kind.name(),
+                    default,
+                    description
+                ),
+                None => println!("    param {}: {}{}", name, parameter.kind.name(), default),
+            }
+        }
+
+        match preset.commands(&presets, &[], 0) {
+            Ok(cmd_list) => {
+                print_commands("    ", &cmd_list.commands);
+                if !cmd_list.encoding.to_string().is_empty() {
+                    println!("    encoding: {}", cmd_list.encoding);
+                }
+                for rendition in &cmd_list.renditions {
+                    println!("    output {} → {}", rendition.name, rendition.output);
+                    print_commands("        ", &rendition.commands);
+                    if !rendition.encoding.to_string().is_empty() {
+                        println!("        encoding: {}", rendition.encoding);
+                    }
+                }
+            }
+            Err(error) => println!("    {}", error),
+        }
+    }
+    Ok(())
+}
+
+/// This function prints the supplied commands, one per line
+///
+/// # Arguments
+///
+/// * `indent` - The indentation of each line
+/// * `commands` - The implementors of `Command` in the order of execution
+fn print_commands(indent: &str, commands: &[Box<dyn Command>]) {
+    for command in commands {
+        println!("{}{}", indent, command.print());
+    }
+}
+
+/// This function reads the presets of a preset file into the supplied list
+///
+/// Returns `()`, or an `Error::Argument` if the content is no valid preset file
+///
+/// # Arguments
+///
+/// * `content` - The content of the preset file
+/// * `source` - The origin of the preset file
+/// * `presets` - The list to add the presets to, replacing presets of the same name
+fn read_presets(content: &str, source: Source, presets: &mut Vec<Preset>) -> Result<()> {
+    let file_error =
+        |message: String| Error::argument(ARG_PRESET, format!("{}: {}", source, message));
+
+    let tables =
+        match toml::from_str::<toml::Value>(content).map_err(|e| file_error(e.to_string()))? {
+            toml::Value::Table(tables) => tables,
+            _ => return Err(file_error(String::from("expected a table of presets"))),
+        };
+    for (name, mut table) in tables {
+        let description = match table.as_table_mut() {
+            Some(table) => match table.remove("description") {
+                Some(toml::Value::String(description)) => Some(description),
+                Some(_) => {
+                    return Err(file_error(format!(
+                        "the description of {} is no string",
+                        name
+                    )))
+                }
+                None => None,
+            },
+            None => return Err(file_error(format!("{} is no table of a preset", name))),
+        };
+        let parameters = match table
+            .as_table_mut()
+            .and_then(|table| table.remove("params"))
+        {
+            Some(params) => read_parameters(params)
+                .map_err(|message| file_error(format!("params of {}: {}", name, message)))?,
+            None => vec![],
+        };
+
+        let preset = Preset {
+            name,
+            description,
+            source: source.clone(),
+            parameters,
+            table,
+        };
+        match presets.iter_mut().find(|known| known.name == preset.name) {
+            Some(known) => *known = preset,
+            None => presets.push(preset),
+        }
+    }
+    Ok(())
+}
+
+/// This function reads the declared parameters of a preset
+///
+/// Returns a new `Vec<(String, Parameter)>` list, or the description of the error as String
+///
+/// # Arguments
+///
+/// * `params` - The `params` table of the preset
+fn read_parameters(params: toml::Value) -> std::result::Result<Vec<(String, Parameter)>, String> {
+    let params = match params {
+        toml::Value::Table(params) => params,
+        _ => return Err(String::from("expected a table of parameters")),
+    };
+
+    params
+        .into_iter()
+        .map(|(name, value)| {
+            let mut parameter: Parameter =
+                value.try_into().map_err(|e| format!("{}: {}", name, e))?;
+            if let Some(default) = parameter.default.take() {
+                let default = typed_default(parameter.kind, &default).ok_or_else(|| {
+                    format!("the default of {} is no {}", name, parameter.kind.name())
+                })?;
+                parameter.default = Some(default);
+            }
+            Ok((name, parameter))
+        })
+        .collect()
+}
+
+/// This function converts the supplied value of a parameter into a TOML value of its type
+///
+/// Returns the `toml::Value`, or `None` if the value can not be parsed into the type
+///
+/// # Arguments
+///
+/// * `kind` - The `Kind` enum as type of the parameter
+/// * `value` - The value as supplied on the command line
+///
+/// # Examples
+/// The example is not compiled, as `typed_value` is private.
+///
+/// ```ignore
+/// assert_eq!(typed_value(Kind::Integer, "800"), Some(toml::Value::Integer(800)));
+/// assert_eq!(typed_value(Kind::Integer, "wide"), None);
+/// ```
+fn typed_value(kind: Kind, value: &str) -> Option<toml::Value> {
+    kind.value(value)
+        .and_then(|value| toml::Value::try_from(value).ok())
+}
+
+/// This function converts the supplied default value of a preset file into a TOML value of the type of the parameter
+///
+/// Integers are accepted as default of a float parameter.
+///
+/// Returns the `toml::Value`, or `None` if the value does not match the type
+///
+/// # Arguments
+///
+/// * `kind` - The `Kind` enum as type of the parameter
+/// * `default` - The default value
+fn typed_default(kind: Kind, default: &toml::Value) -> Option<toml::Value> {
+    serde_json::to_value(default)
+        .ok()
+        .and_then(|default| kind.text(&default))
+        .and_then(|text| typed_value(kind, &text))
+}
+
+/// This function replaces the placeholders of the supplied parameters within all strings of a TOML value
+///
+/// A string, which consists of a single placeholder, is replaced by the typed value of the parameter.
+///
+/// Returns the `toml::Value` with the substituted placeholders
+///
+/// # Arguments
+///
+/// * `value` - The TOML value, usually the table of a preset
+/// * `values` - The values of the parameters by name
+///
+/// # Examples
+/// The example is not compiled, as `substitute` is private.
+///
+/// ```ignore
+/// let values = vec![(String::from("width"), toml::Value::Integer(800))];
+/// assert_eq!(substitute(toml::Value::from("{width}"), &values), toml::Value::Integer(800));
+/// assert_eq!(substitute(toml::Value::from("w{width}"), &values), toml::Value::from("w800"));
+/// ```
+fn substitute(value: toml::Value, values: &[(String, toml::Value)]) -> toml::Value {
+    match value {
+        toml::Value::String(text) => {
+            let placeholder = |name: &str| format!("{{{}}}", name);
+            if let Some((_, value)) = values.iter().find(|(name, _)| text == placeholder(name)) {
+                return value.clone();
+            }
+            // The text is scanned once, so placeholders within the supplied values are kept
+            let mut substituted = String::new();
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find('{') {
+                substituted.push_str(&rest[..start]);
+                rest = &rest[start..];
+                let end = rest.find('}').unwrap_or(0);
+                match values
+                    .iter()
+                    .find(|(name, _)| placeholder(name) == rest[..=end])
+                {
+                    Some((_, toml::Value::String(value))) => substituted.push_str(value),
+                    Some((_, value)) => substituted.push_str(&value.to_string()),
+                    None => {
+                        substituted.push('{');
+                        rest = &rest[1..];
+                        continue;
+                    }
+                }
+                rest = &rest[end + 1..];
+            }
+            substituted.push_str(rest);
+            toml::Value::String(substituted)
+        }
+        toml::Value::Array(array) => toml::Value::Array(
+            array
+                .into_iter()
+                .map(|value| substitute(value, values))
+                .collect(),
+        ),
+        toml::Value::Table(table) => toml::Value::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, substitute(value, values)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// This function returns the path of the per-user preset file, if the configuration directory of the user is known
+fn user_preset_file() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(config_dir.join(USER_PRESET_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This function reads the presets of the supplied preset file
+    fn presets(content: &str) -> Vec<Preset> {
+        let mut presets = vec![];
+        read_presets(content, Source::BuiltIn, &mut presets).unwrap();
+        presets
+    }
+
+    /// This function returns the message of the error of expanding the supplied preset
+    fn error(presets: &[Preset], name: &str) -> String {
+        match find_preset(presets, name)
+            .unwrap()
+            .commands(presets, &[], 0)
+        {
+            Err(Error::Argument { message, .. }) => message,
+            _ => panic!("expected an error expanding the preset {}", name),
+        }
+    }
+
+    #[test]
+    fn presets_including_themselves_are_rejected() {
+        let presets = presets(
+            r#"
+            [a]
+            step = [{ op = "preset", name = "a" }]
+            "#,
+        );
+        assert_eq!(error(&presets, "a"), "the preset a includes itself: a → a");
+    }
+
+    #[test]
+    fn presets_including_each_other_are_rejected() {
+        let presets = presets(
+            r#"
+            [a]
+            step = [{ op = "blur", sigma = 1.0 }, { op = "preset", name = "b" }]
+            [b]
+            step = [{ op = "preset", name = "a" }]
+            "#,
+        );
+        assert_eq!(
+            error(&presets, "a"),
+            "the preset a includes itself: a → b → a"
+        );
+        assert_eq!(
+            error(&presets, "b"),
+            "the preset b includes itself: b → a → b"
+        );
+    }
+
+    #[test]
+    fn included_presets_keep_the_order_of_their_steps() {
+        let presets = presets(
+            r#"
+            [outer]
+            step = [{ op = "invert" }, { op = "preset", name = "inner" }, { op = "rotate90" }]
+            [inner]
+            step = [{ op = "blur", sigma = 1.0 }, { op = "preset", name = "leaf" }]
+            [leaf]
+            step = [{ op = "brighten", value = 10 }]
+            "#,
+        );
+        let cmd_list = find_preset(&presets, "outer")
+            .unwrap()
+            .commands(&presets, &[], 0)
+            .unwrap();
+        let names: Vec<&str> = cmd_list
+            .commands
+            .iter()
+            .map(|command| command.describe().command)
+            .collect();
+        assert_eq!(names, ["invert", "blur", "brighten", "rotate"]);
+    }
+
+    #[test]
+    fn encoding_of_the_including_preset_takes_precedence() {
+        let presets = presets(
+            r#"
+            [outer]
+            quality = 50
+            step = [{ op = "preset", name = "first" }, { op = "preset", name = "second" }]
+            [first]
+            quality = 90
+            png_compression = "fast"
+            max_bytes = 1000
+            output = [{ name = "small", path = "small.jpg" }]
+            [second]
+            quality = 95
+            png_compression = "best"
+            output = [{ name = "large", path = "large.jpg" }]
+            "#,
+        );
+        let cmd_list = find_preset(&presets, "outer")
+            .unwrap()
+            .commands(&presets, &[], 0)
+            .unwrap();
+        assert_eq!(cmd_list.encoding.quality, Some(50));
+        assert_eq!(cmd_list.encoding.max_bytes, Some(1000));
+        assert_eq!(
+            format!("{:?}", cmd_list.encoding.png_compression),
+            format!("{:?}", Some(image::png::CompressionType::Best))
+        );
+        let outputs: Vec<&str> = cmd_list
+            .renditions
+            .iter()
+            .map(|rendition| rendition.name.as_str())
+            .collect();
+        assert_eq!(outputs, ["small", "large"]);
+    }
+
+    #[test]
+    fn built_in_copyright_notice_fits_all_sizes() {
+        let presets = load_presets().unwrap();
+        let preset = find_preset(&presets, "app_copyright").unwrap();
+        for arguments in &[vec![], vec!["width=80", "height=60"], vec!["margin=4"]] {
+            let cmd_list = preset.commands(&presets, arguments, 0).unwrap();
+            let (mut width, mut height) = (1000, 700);
+            for command in &cmd_list.commands {
+                assert!(command.validate(width, height).is_ok(), "{:?}", arguments);
+                let dimensions = command.dimensions(width, height);
+                width = dimensions.0;
+                height = dimensions.1;
+            }
+        }
+        let cmd_list = preset.commands(&presets, &["margin=4"], 0).unwrap();
+        assert_eq!(
+            cmd_list.commands[2].describe().parameters["position"],
+            serde_json::json!({ "anchor": "bottom_right", "x_offset": 4, "y_offset": 4 })
+        );
+    }
+
+    /// The preset file of the parameter tests, whose parameters are substituted into a text step
+    const PARAMETERS: &str = r#"
+        [notice]
+        [notice.params]
+        owner = { type = "string" }
+        year = { type = "unsigned", default = 2020 }
+        shade = { type = "float", default = 1 }
+        [[notice.step]]
+        op = "text_br"
+        text = "(c) {year} {owner}"
+        x_offset = "{year}"
+        y_offset = 5
+        [[notice.step]]
+        op = "blur"
+        sigma = "{shade}"
+    "#;
+
+    /// This function returns the message of the error of expanding the preset notice with the supplied parameters
+    fn parameter_error(arguments: &[&str]) -> String {
+        let presets = presets(PARAMETERS);
+        match find_preset(&presets, "notice")
+            .unwrap()
+            .commands(&presets, arguments, 0)
+        {
+            Err(Error::Argument { message, .. }) => message,
+            _ => panic!("expected an error for the parameters {:?}", arguments),
+        }
+    }
+
+    #[test]
+    fn parameters_are_substituted_by_type() {
+        let presets = presets(PARAMETERS);
+        let cmd_list = find_preset(&presets, "notice")
+            .unwrap()
+            .commands(&presets, &["owner=ACME {year}", "shade=2.5"], 0)
+            .unwrap();
+        assert_eq!(
+            cmd_list.commands[0].operation(),
+            Some((
+                "text_br",
+                vec![
+                    String::from("(c) 2020 ACME {year}"),
+                    String::from("2020"),
+                    String::from("5"),
+                ]
+            ))
+        );
+        assert_eq!(
+            cmd_list.commands[1].operation(),
+            Some(("blur", vec![String::from("2.5")]))
+        );
+    }
+
+    #[test]
+    fn parameters_of_the_wrong_type_are_rejected() {
+        assert_eq!(
+            parameter_error(&["owner=ACME", "year=-1"]),
+            "notice: the parameter year expects a value of type u32, got -1"
+        );
+        assert_eq!(
+            parameter_error(&["owner=ACME", "shade=dark"]),
+            "notice: the parameter shade expects a value of type f32, got dark"
+        );
+        let content = r#"
+            [notice]
+            params = { year = { type = "unsigned", default = "2020" } }
+        "#;
+        match read_presets(content, Source::BuiltIn, &mut vec![]) {
+            Err(Error::Argument { message, .. }) => assert_eq!(
+                message,
+                "built-in: params of notice: the default of year is no u32"
+            ),
+            _ => panic!("expected an error for a mistyped default"),
+        }
+    }
+
+    #[test]
+    fn missing_and_unknown_parameters_are_rejected() {
+        assert_eq!(
+            parameter_error(&[]),
+            "notice: the parameter owner of type text is required"
+        );
+        assert_eq!(
+            parameter_error(&["owner=ACME", "color=red"]),
+            "notice: unknown parameter color, expected one of owner, shade, year"
+        );
+        assert_eq!(
+            parameter_error(&["owner=ACME", "owner=Thumbnailer"]),
+            "notice: the parameter owner is supplied twice"
+        );
+        assert_eq!(
+            parameter_error(&["owner"]),
+            "notice: expected a parameter as name=value, got owner"
+        );
+    }
+}
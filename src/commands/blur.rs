@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{Kind, Operation, Parameter, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the blur-command as a struct
 pub struct CmdBlur {
@@ -19,6 +22,18 @@ impl CmdBlur {
     }
 }
 
+/// The operations of the blur-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "blur",
+        &[
+            Parameter::new("sigma", Kind::Float),
+        ],
+        "Performs a Gaussian blur on the supplied image(s). sigma as f32 is a measure of how much to blur by.",
+        create,
+    ),
+];
+
 impl Command for CmdBlur {
     /// This function calls the actual blur command, depending on the `sigma` given by `CmdBlur`-struct.
     ///
@@ -40,6 +55,7 @@ impl Command for CmdBlur {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdBlur, Command};
     /// let index = 1;
     /// let blur = CmdBlur::new(index, 0.5);
     /// println!("index = {}", blur.get_index());
@@ -57,10 +73,35 @@ impl Command for CmdBlur {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdBlur, Command};
     /// let blur = CmdBlur::new(1, 0.5);
     /// println!("{}", blur.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. blur:\t\tsigma = {}", self.index, self.sigma)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdBlur`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdBlur`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "blur",
+            parameters: json!({ "sigma": self.sigma }),
+        }
+    }
+}
+
+/// This function creates the blur-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdBlur::new(values.index(), values.get("sigma")?)))
 }
@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{Kind, Operation, Parameter, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the brighten-command as a struct
 pub struct CmdBrighten {
@@ -19,6 +22,18 @@ impl CmdBrighten {
     }
 }
 
+/// The operations of the brighten-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "brighten",
+        &[
+            Parameter::new("value", Kind::Integer),
+        ],
+        "Brightens the supplied image(s). value as i32 is the amount to brighten each pixel by. Negative values decrease the brightness and positive values increase it.",
+        create,
+    ),
+];
+
 impl Command for CmdBrighten {
     /// This function calls the actual brighten command, depending on the `value` given by `CmdBrighten`-struct.
     ///
@@ -40,10 +55,12 @@ impl Command for CmdBrighten {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdBrighten, Command};
     /// let index = 2;
     /// let brighten = CmdBrighten::new(index, 3);
     /// println!("index = {}", brighten.get_index());
     /// assert_eq!(brighten.get_index(), 2, "testing brighten.get_index() with index = {}", index);
+    /// ```
     fn get_index(&self) -> u32 {
         self.index
     }
@@ -56,10 +73,38 @@ impl Command for CmdBrighten {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdBrighten, Command};
     /// let brighten = CmdBrighten::new(2, 3);
     /// println!("{}", brighten.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. brighten:\t\tvalue = {}", self.index, self.value)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdBrighten`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdBrighten`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "brighten",
+            parameters: json!({ "value": self.value }),
+        }
+    }
+}
+
+/// This function creates the brighten-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdBrighten::new(
+        values.index(),
+        values.get("value")?,
+    )))
 }
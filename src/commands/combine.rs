@@ -1,13 +1,26 @@
-use thumbnailer::{BoxPosition, GenericThumbnail, StaticThumbnail};
+use std::path::Path;
 
-use crate::commands::Command;
+use serde_json::json;
+use thumbnailer::{BoxPosition, GenericThumbnail, StaticThumbnail, Thumbnail};
+
+use crate::commands::registry::{
+    Expansion, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{
+    describe_position, operation_name, position_corner, validate_position, Command, Description,
+};
+use crate::error::{Error, Result};
 
 /// Representation of the combine-command as a struct
 pub struct CmdCombine {
     /// Contains the `index` as u32 of arguments list
     index: u32,
+    /// Contains the path of the photo as String
+    path: String,
     /// Contains the `StaticThumbmnail` struct as image
     image: StaticThumbnail,
+    /// Contains the width and height of the photo
+    size: (u32, u32),
     /// Contains the `BoxPosition` enum as option
     position: BoxPosition,
 }
@@ -15,17 +28,107 @@ pub struct CmdCombine {
 impl CmdCombine {
     /// Returns a new `CmdCombine` struct with defined:
     /// * `ìndex`: position of arguments list
+    /// * `path`: path of the photo, which was loaded as `image`
     /// * `image`: `StaticThumbmnail` struct as image
+    /// * `size`: width and height of the photo
     /// * `position`: `BoxPosition` enum as option
-    pub fn new(index: u32, image: StaticThumbnail, position: BoxPosition) -> Self {
+    pub fn new(
+        index: u32,
+        path: String,
+        image: StaticThumbnail,
+        size: (u32, u32),
+        position: BoxPosition,
+    ) -> Self {
         CmdCombine {
             index,
+            path,
             image,
+            size,
             position,
         }
     }
+
+    /// This function loads the photo, which should be inserted by the combine-command, and creates the combine-command
+    ///
+    /// The dimensions of the photo are read once, so checking the photo against the images of a batch does not read it again.
+    ///
+    /// Returns a new `CmdCombine` struct, or an `Error::Load` if the photo could not be loaded
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The `index` as u32 of arguments list
+    /// * `image` - The path of the photo to load
+    /// * `position` - The `BoxPosition` enum as option
+    /// * `arg` - The argument name (or pipeline entry) of the combine-command to report errors
+    pub fn load(index: u32, image: &str, position: BoxPosition, arg: &str) -> Result<Self> {
+        let load_error = || Error::Load {
+            arg: String::from(arg),
+            path: String::from(image),
+        };
+
+        // The photo is decoded once, its size is taken from the loaded thumbnail
+        let mut thumbnail =
+            Thumbnail::load(Path::new(image).to_path_buf()).map_err(|_| load_error())?;
+        let size = thumbnail.get_size();
+        let photo = thumbnail.clone_static_copy().ok_or_else(load_error)?;
+        Ok(CmdCombine::new(
+            index,
+            String::from(image),
+            photo,
+            size,
+            position,
+        ))
+    }
 }
 
+/// The parameters of the combine-command, shared by all positions
+const PARAMETERS: &[Parameter] = &[
+    Parameter::new("image", Kind::Text),
+    Parameter::new("x_offset", Kind::Unsigned),
+    Parameter::new("y_offset", Kind::Unsigned),
+];
+
+/// The operations of the combine-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "combine_tl",
+        PARAMETERS,
+        "Inserts a photo, such as a logo given as image path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP LEFT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "combine_tr",
+        PARAMETERS,
+        "Inserts a photo, such as a logo given as image path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP RIGHT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "combine_bl",
+        PARAMETERS,
+        "Inserts a photo, such as a logo given as image path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM LEFT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "combine_br",
+        PARAMETERS,
+        "Inserts a photo, such as a logo given as image path, into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM RIGHT corner of the photo.",
+        create,
+    ),
+];
+
+/// The shorthands of the combine-command in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "combine",
+    &[
+        Parameter::new("pos", Kind::Text),
+        Parameter::new("x", Kind::Unsigned),
+        Parameter::new("y", Kind::Unsigned),
+        Parameter::new("image", Kind::Text),
+    ],
+    "combine:pos,x,y,image with pos tl, tr, bl or br",
+    expand,
+)];
+
 impl Command for CmdCombine {
     /// This function calls the actual combine command, depending on the values given by the members of `CmdCombine`-struct.
     ///
@@ -47,8 +150,10 @@ impl Command for CmdCombine {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::BoxPosition;
+    /// # use thumbnailer_cli::commands::{CmdCombine, Command};
     /// let index = 4;
-    /// let combine = CmdCombine { index, image: TODO, position: BoxPosition::TopLeft(34, 56) };
+    /// let combine = CmdCombine::load(index, "resources/tests/test_small.png", BoxPosition::TopLeft(34, 56), "combine").unwrap();
     /// println!("index = {}", combine.get_index());
     /// assert_eq!(combine.get_index(), 4, "testing combine.get_index() with index = {}", index);
     /// ```
@@ -64,13 +169,116 @@ impl Command for CmdCombine {
     ///
     /// # Examples
     /// ```
-    /// let combine = CmdCombine { index: 4, image: TODO, position: BoxPosition::TopLeft(34, 56) };
+    /// # use thumbnailer::BoxPosition;
+    /// # use thumbnailer_cli::commands::{CmdCombine, Command};
+    /// let combine = CmdCombine::load(4, "resources/tests/test_small.png", BoxPosition::TopLeft(34, 56), "combine").unwrap();
     /// println!("{}", combine.print());
     /// ```
     fn print(&self) -> String {
         format!(
-            "► {:02}. combine:\tposition = {:?}",
-            self.index, self.position
+            "► {:02}. combine:\timage = {}, position = {:?}",
+            self.index, self.path, self.position
         )
     }
+
+    /// This function returns the path of the photo, which is inserted by the combine-command.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCombine`-struct
+    fn dependencies(&self) -> Vec<&Path> {
+        vec![Path::new(&self.path)]
+    }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdCombine`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCombine`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "combine",
+            parameters: json!({
+                "image": self.path,
+                "position": describe_position(&self.position),
+            }),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdCombine`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCombine`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        let (corner, x_offset, y_offset) = position_corner(&self.position);
+        let name = operation_name(OPERATIONS, "combine_", corner)?;
+        Some((
+            name,
+            vec![
+                self.path.clone(),
+                x_offset.to_string(),
+                y_offset.to_string(),
+            ],
+        ))
+    }
+
+    /// This function checks, whether the photo lies within an image of the supplied dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCombine`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn validate(&self, width: u32, height: u32) -> std::result::Result<(), String> {
+        let what = format!("photo of {}x{}", self.size.0, self.size.1);
+        validate_position(&what, &self.position, self.size, width, height)
+    }
+}
+
+/// This function selects the combine-operation of the supplied corner
+///
+/// Returns the name of the operation and its values, or an `Invalid` struct if a value is omitted or the corner is unknown
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    let name = operation_name(OPERATIONS, "combine_", values.required("pos")?)
+        .ok_or_else(|| values.invalid("pos", "one of tl, tr, bl, br"))?;
+    let x_offset = values.required("x")?;
+    let y_offset = values.required("y")?;
+    let image = values.required("image")?;
+    Ok((
+        name,
+        vec![
+            String::from(image),
+            String::from(x_offset),
+            String::from(y_offset),
+        ],
+    ))
+}
+
+/// This function creates the combine-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error` if a value could not be parsed or the photo could not be loaded
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let image = values.get::<String>("image")?;
+    let x_offset = values.get::<u32>("x_offset")?;
+    let y_offset = values.get::<u32>("y_offset")?;
+
+    let position = match values.name() {
+        "combine_tr" => BoxPosition::TopRight(x_offset, y_offset),
+        "combine_bl" => BoxPosition::BottomLeft(x_offset, y_offset),
+        "combine_br" => BoxPosition::BottomRight(x_offset, y_offset),
+        _ => BoxPosition::TopLeft(x_offset, y_offset),
+    };
+
+    let combine = CmdCombine::load(values.index(), &image, position, values.entry())?;
+    Ok(Box::new(combine))
 }
@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{Kind, Operation, Parameter, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the contrast-command as a struct
 pub struct CmdContrast {
@@ -19,6 +22,18 @@ impl CmdContrast {
     }
 }
 
+/// The operations of the contrast-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "contrast",
+        &[
+            Parameter::new("value", Kind::Float),
+        ],
+        "Adjusts the contrast of the supplied image(s). value as f32 is the amount to adjust the contrast by. Negative values decrease the contrast and positive values increase the contrast.",
+        create,
+    ),
+];
+
 impl Command for CmdContrast {
     /// This function calls the actual contrast command, depending on the `value` given by `CmdContrast`-struct.
     ///
@@ -40,6 +55,7 @@ impl Command for CmdContrast {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdContrast, Command};
     /// let index = 3;
     /// let contrast = CmdContrast::new(index, 0.8);
     /// println!("index = {}", contrast.get_index());
@@ -57,10 +73,38 @@ impl Command for CmdContrast {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdContrast, Command};
     /// let contrast = CmdContrast::new(3, 0.8);
     /// println!("{}", contrast.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. contrast:\tvalue = {}", self.index, self.value)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdContrast`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdContrast`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "contrast",
+            parameters: json!({ "value": self.value }),
+        }
+    }
+}
+
+/// This function creates the contrast-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdContrast::new(
+        values.index(),
+        values.get("value")?,
+    )))
 }
@@ -1,6 +1,11 @@
+use serde_json::json;
 use thumbnailer::{Crop, GenericThumbnail};
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Invalid, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the crop-command as a struct
 pub struct CmdCrop {
@@ -19,6 +24,44 @@ impl CmdCrop {
     }
 }
 
+/// The operations of the crop-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "crop_box",
+        &[
+            Parameter::new("x", Kind::Unsigned),
+            Parameter::new("y", Kind::Unsigned),
+            Parameter::new("width", Kind::Unsigned),
+            Parameter::new("height", Kind::Unsigned),
+        ],
+        "Crops the supplied image(s) to the given width as u32 and height as u32. x as u32 is the horizontal and y as u32 the vertical offset.",
+        create,
+    ),
+    Operation::new(
+        "crop_ratio",
+        &[
+            Parameter::new("x_ratio", Kind::Float),
+            Parameter::new("y_ratio", Kind::Float),
+        ],
+        "Crops the supplied image(s) to the given ratio 'x_ratio:y_ratio'. x_ratio as f32 is representing the horizontal and y_ratio as f32 the vertical.",
+        create,
+    ),
+];
+
+/// The shorthands of the crop-command in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "crop",
+    &[
+        Parameter::new("x", Kind::Unsigned),
+        Parameter::new("y", Kind::Unsigned),
+        Parameter::new("width", Kind::Unsigned),
+        Parameter::new("height", Kind::Unsigned),
+        Parameter::new("ratio", Kind::Text),
+    ],
+    "crop:ratio=x:y or crop:x,y,width,height",
+    expand,
+)];
+
 impl Command for CmdCrop {
     /// This function calls the actual crop command, depending on the values given by the members of `CmdCrop`-struct.
     ///
@@ -39,8 +82,10 @@ impl Command for CmdCrop {
     /// * `&self` - the `CmdCrop`-struct
     /// # Examples
     /// ```
+    /// # use thumbnailer::Crop;
+    /// # use thumbnailer_cli::commands::{CmdCrop, Command};
     /// let index = 5;
-    /// let crop = CmdCrop::new(index, Crop::Ratio(4, 3));
+    /// let crop = CmdCrop::new(index, Crop::Ratio(4.0, 3.0));
     /// println!("index = {}", crop.get_index());
     /// assert_eq!(crop.get_index(), 5, "testing crop.get_index() with index = {}", index);
     /// ```
@@ -56,10 +101,172 @@ impl Command for CmdCrop {
     ///
     /// # Examples
     /// ```
-    /// let crop = CmdCrop::new(5, Crop::Ratio(4, 3));
+    /// # use thumbnailer::Crop;
+    /// # use thumbnailer_cli::commands::{CmdCrop, Command};
+    /// let crop = CmdCrop::new(5, Crop::Ratio(4.0, 3.0));
     /// println!("{}", crop.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. crop:\t\t{:?}", self.index, self.config)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdCrop`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCrop`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "crop",
+            parameters: match self.config {
+                Crop::Box(x, y, width, height) => {
+                    json!({ "x": x, "y": y, "width": width, "height": height })
+                }
+                Crop::Ratio(x_ratio, y_ratio) => json!({ "x_ratio": x_ratio, "y_ratio": y_ratio }),
+            },
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdCrop`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCrop`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        Some(match self.config {
+            Crop::Box(x, y, width, height) => (
+                "crop_box",
+                vec![
+                    x.to_string(),
+                    y.to_string(),
+                    width.to_string(),
+                    height.to_string(),
+                ],
+            ),
+            Crop::Ratio(x_ratio, y_ratio) => {
+                ("crop_ratio", vec![x_ratio.to_string(), y_ratio.to_string()])
+            }
+        })
+    }
+
+    /// This function returns the dimensions of the image after executing the command, depending on the values given by the members of `CmdCrop`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCrop`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.config {
+            // The box is clipped at the borders of the image
+            Crop::Box(x, y, crop_width, crop_height) => (
+                crop_width.min(width.saturating_sub(x)),
+                crop_height.min(height.saturating_sub(y)),
+            ),
+            // The largest centered area with the ratio is kept
+            Crop::Ratio(x_ratio, y_ratio) => {
+                let ratio = x_ratio as f64 / y_ratio as f64;
+                if width as f64 / height as f64 > ratio {
+                    ((height as f64 * ratio).round() as u32, height)
+                } else {
+                    (width, (width as f64 / ratio).round() as u32)
+                }
+            }
+        }
+    }
+
+    /// This function checks, whether the crop box lies within an image of the supplied dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdCrop`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn validate(&self, width: u32, height: u32) -> std::result::Result<(), String> {
+        match self.config {
+            Crop::Box(x, y, crop_width, crop_height) => {
+                if crop_width == 0 || crop_height == 0 {
+                    return Err(format!(
+                        "the crop box of {}x{} is empty",
+                        crop_width, crop_height
+                    ));
+                }
+                if x as u64 + crop_width as u64 > width as u64
+                    || y as u64 + crop_height as u64 > height as u64
+                {
+                    return Err(format!(
+                        "the crop box of {}x{} at ({}, {}) exceeds the image of {}x{}",
+                        crop_width, crop_height, x, y, width, height
+                    ));
+                }
+            }
+            Crop::Ratio(x_ratio, y_ratio) => {
+                if !(x_ratio > 0.0 && y_ratio > 0.0) {
+                    return Err(format!(
+                        "the crop ratio {}:{} is not positive",
+                        x_ratio, y_ratio
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// This function selects the crop_ratio-operation for a ratio, and the crop_box-operation otherwise
+///
+/// Returns the name of the operation and its values, or an `Invalid` struct if a value is omitted or both a ratio and a box are supplied
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    if values.get("ratio").is_none() {
+        let x = values
+            .required("x")
+            .map_err(|_| values.missing("x or ratio"))?;
+        let box_values = [
+            x,
+            values.required("y")?,
+            values.required("width")?,
+            values.required("height")?,
+        ];
+        return Ok((
+            "crop_box",
+            box_values
+                .iter()
+                .map(|value| String::from(*value))
+                .collect(),
+        ));
+    }
+    if values.get("x").is_some() {
+        return Err(Invalid {
+            parameter: Some("ratio"),
+            message: String::from("crop expects either a ratio or x, y, width and height"),
+        });
+    }
+    let (x_ratio, y_ratio) = values.pair("ratio", ':', Kind::Float, "x_ratio:y_ratio")?;
+    Ok(("crop_ratio", vec![x_ratio, y_ratio]))
+}
+
+/// This function creates the crop-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let config = match values.name() {
+        "crop_ratio" => Crop::Ratio(values.get("x_ratio")?, values.get("y_ratio")?),
+        _ => Crop::Box(
+            values.get("x")?,
+            values.get("y")?,
+            values.get("width")?,
+            values.get("height")?,
+        ),
+    };
+
+    Ok(Box::new(CmdCrop::new(values.index(), config)))
 }
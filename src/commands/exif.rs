@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::{Exif, GenericThumbnail};
 
-use crate::commands::Command;
+use crate::commands::registry::{Operation, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the exif-command as a struct
 pub struct CmdExif {
@@ -19,6 +22,17 @@ impl CmdExif {
     }
 }
 
+/// Name of the exif-operation and its argument
+pub const ARG_EXIF: &str = "exif";
+
+/// The operations of the exif-command
+pub const OPERATIONS: &[Operation] = &[Operation::new(
+    ARG_EXIF,
+    &[],
+    "The metadata of the target file is taken from the source file and not removed as usual.",
+    create,
+)];
+
 impl Command for CmdExif {
     /// This function calls the actual exif command, depending on the values given by the members of `CmdExif`-struct.
     ///
@@ -40,10 +54,12 @@ impl Command for CmdExif {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Exif;
+    /// # use thumbnailer_cli::commands::{CmdExif, Command};
     /// let index = 6;
     /// let exif = CmdExif::new(index, Exif::Keep);
     /// println!("index = {}", exif.get_index());
-    /// assert_eq!(exif.get_index(), 1, "testing exif.get_index() with index = {}", index);
+    /// assert_eq!(exif.get_index(), 6, "testing exif.get_index() with index = {}", index);
     /// ```
     fn get_index(&self) -> u32 {
         self.index
@@ -57,10 +73,72 @@ impl Command for CmdExif {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Exif;
+    /// # use thumbnailer_cli::commands::{CmdExif, Command};
     /// let exif = CmdExif::new(6, Exif::Keep);
     /// println!("{}", exif.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. exif:\t\t{:?}", self.index, self.metadata)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdExif`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdExif`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: ARG_EXIF,
+            parameters: match &self.metadata {
+                Exif::Keep => json!({ "metadata": "keep" }),
+                Exif::Clear => json!({ "metadata": "clear" }),
+                Exif::Whitelist(tags) => json!({ "metadata": "whitelist", "tags": tags }),
+                Exif::Blacklist(tags) => json!({ "metadata": "blacklist", "tags": tags }),
+            },
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, depending on the values given by the members of `CmdExif`-struct.
+    ///
+    /// The exif-operation only keeps the metadata, the other options can not be created by an operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdExif`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        match self.metadata {
+            Exif::Keep => Some((ARG_EXIF, vec![])),
+            _ => None,
+        }
+    }
+}
+
+/// This function creates the exif-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdExif::new(values.index(), Exif::Keep)))
+}
+
+/// This function checks, whether the metadata of the source file is kept by the supplied commands
+///
+/// The last exif-command decides, metadata is removed as usual without an exif-command.
+///
+/// # Arguments
+///
+/// * `commands` - The implementors of `Command` in the order of execution
+pub(crate) fn keeps_metadata<'c>(
+    commands: impl DoubleEndedIterator<Item = &'c Box<dyn Command>>,
+) -> bool {
+    commands
+        .rev()
+        .map(|command| command.describe())
+        .find(|description| description.command == ARG_EXIF)
+        .is_some_and(|description| description.parameters["metadata"] != "clear")
 }
@@ -1,6 +1,11 @@
+use serde_json::json;
 use thumbnailer::{GenericThumbnail, Orientation};
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the flip-command as a struct
 pub struct CmdFlip {
@@ -19,6 +24,30 @@ impl CmdFlip {
     }
 }
 
+/// The operations of the flip-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "flip_horizontal",
+        &[],
+        "Flip the supplied image(s) horizontally.",
+        create,
+    ),
+    Operation::new(
+        "flip_vertical",
+        &[],
+        "Flip the supplied image(s) vertically.",
+        create,
+    ),
+];
+
+/// The shorthands of the flip-command in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "flip",
+    &[Parameter::new("axis", Kind::Text)],
+    "flip:h|v",
+    expand,
+)];
+
 impl Command for CmdFlip {
     /// This function calls the actual flip command, depending on the value given by the `CmdFlip`-struct.
     ///
@@ -40,6 +69,8 @@ impl Command for CmdFlip {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Orientation;
+    /// # use thumbnailer_cli::commands::{CmdFlip, Command};
     /// let index = 7;
     /// let flip = CmdFlip::new(index, Orientation::Horizontal);
     /// println!("index = {}", flip.get_index());
@@ -57,6 +88,8 @@ impl Command for CmdFlip {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Orientation;
+    /// # use thumbnailer_cli::commands::{CmdFlip, Command};
     /// let flip = CmdFlip::new(7, Orientation::Horizontal);
     /// println!("{}", flip.print());
     /// ```
@@ -66,4 +99,60 @@ impl Command for CmdFlip {
             self.index, self.orientation
         )
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdFlip`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdFlip`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "flip",
+            parameters: json!({ "orientation": format!("{:?}", self.orientation).to_lowercase() }),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdFlip`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdFlip`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        match self.orientation {
+            Orientation::Horizontal => Some(("flip_horizontal", vec![])),
+            Orientation::Vertical => Some(("flip_vertical", vec![])),
+        }
+    }
+}
+
+/// This function selects the flip-operation of the supplied axis
+///
+/// Returns the name of the operation, or an `Invalid` struct if the axis is omitted or unknown
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    match values.required("axis")? {
+        "h" | "horizontal" => Ok(("flip_horizontal", vec![])),
+        "v" | "vertical" => Ok(("flip_vertical", vec![])),
+        _ => Err(values.invalid("axis", "h or v")),
+    }
+}
+
+/// This function creates the flip-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let orientation = match values.name() {
+        "flip_vertical" => Orientation::Vertical,
+        _ => Orientation::Horizontal,
+    };
+
+    Ok(Box::new(CmdFlip::new(values.index(), orientation)))
 }
@@ -1,6 +1,11 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the huerotate-command as a struct
 pub struct CmdHuerotate {
@@ -19,6 +24,26 @@ impl CmdHuerotate {
     }
 }
 
+/// The operations of the huerotate-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "huerotate",
+        &[
+            Parameter::new("value", Kind::Integer),
+        ],
+        "Hue rotate the supplied image(s). value as i32 is the degrees to rotate each pixel by. 0 and 360 do nothing, the rest rotates by the given degree value.",
+        create,
+    ),
+];
+
+/// The shorthands of the huerotate-command in the `--ops` language, which name the value by its meaning
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "huerotate",
+    &[Parameter::new("degree", Kind::Integer)],
+    "huerotate:degree",
+    expand,
+)];
+
 impl Command for CmdHuerotate {
     /// This function calls the actual huerotate command, depending on the `degree` given by the `CmdHuerotate`-struct.
     ///
@@ -40,6 +65,7 @@ impl Command for CmdHuerotate {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdHuerotate, Command};
     /// let index = 8;
     /// let huerotate = CmdHuerotate::new(index, 90);
     /// println!("index = {}", huerotate.get_index());
@@ -57,10 +83,58 @@ impl Command for CmdHuerotate {
     ///
     /// # Examples
     /// ```
-    /// let huerotate = CmdBlur::new(8, 90);
+    /// # use thumbnailer_cli::commands::{CmdHuerotate, Command};
+    /// let huerotate = CmdHuerotate::new(8, 90);
     /// println!("{}", huerotate.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. huerotate:\tdegree = {}", self.index, self.degree)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdHuerotate`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdHuerotate`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "huerotate",
+            parameters: json!({ "degree": self.degree }),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdHuerotate`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdHuerotate`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        Some(("huerotate", vec![self.degree.to_string()]))
+    }
+}
+
+/// This function selects the huerotate-operation with the supplied degree as its value
+///
+/// Returns the name of the operation and its value, or an `Invalid` struct if the degree is omitted
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    Ok(("huerotate", vec![String::from(values.required("degree")?)]))
+}
+
+/// This function creates the huerotate-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdHuerotate::new(
+        values.index(),
+        values.get("value")?,
+    )))
 }
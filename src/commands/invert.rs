@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{Operation, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the invert-command as a struct
 pub struct CmdInvert {
@@ -16,6 +19,14 @@ impl CmdInvert {
     }
 }
 
+/// The operations of the invert-command
+pub const OPERATIONS: &[Operation] = &[Operation::new(
+    "invert",
+    &[],
+    "Invert each pixel within the supplied image(s).",
+    create,
+)];
+
 impl Command for CmdInvert {
     /// This function calls the actual invert command.
     ///
@@ -37,6 +48,7 @@ impl Command for CmdInvert {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdInvert, Command};
     /// let index = 9;
     /// let invert = CmdInvert::new(index);
     /// println!("index = {}", invert.get_index());
@@ -54,10 +66,35 @@ impl Command for CmdInvert {
     ///
     /// # Examples
     /// ```
-    /// let invert = CmdBlur::new(9);
+    /// # use thumbnailer_cli::commands::{CmdInvert, Command};
+    /// let invert = CmdInvert::new(9);
     /// println!("{}", invert.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. invert", self.index)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdInvert`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdInvert`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "invert",
+            parameters: json!({}),
+        }
+    }
+}
+
+/// This function creates the invert-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdInvert::new(values.index())))
 }
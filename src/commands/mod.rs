@@ -1,44 +1,59 @@
 use std::cmp::Eq;
 use std::cmp::Ordering;
+use std::path::Path;
 
-use thumbnailer::GenericThumbnail;
-
-pub use blur::CmdBlur;
-pub use brighten::CmdBrighten;
-pub use combine::CmdCombine;
-pub use contrast::CmdContrast;
-pub use crop::CmdCrop;
-pub use exif::CmdExif;
-pub use flip::CmdFlip;
-pub use huerotate::CmdHuerotate;
-pub use invert::CmdInvert;
-pub use resize::CmdResize;
-pub use resize_filter::CmdResizeFilter;
-pub use rotate::CmdRotate;
-pub use text::CmdText;
-pub use unsharpen::CmdUnsharpen;
+use serde::Serialize;
+use serde_json::{json, Value};
+use thumbnailer::{BoxPosition, GenericThumbnail};
+
+use crate::commands::registry::{find_operation, Operation, Shorthand};
+
+/// This macro declares the modules of all commands, re-exports their commands and collects their operations and shorthands
+///
+/// Each entry names the module and its command, followed by `with SHORTHANDS` if the module declares shorthands of the `--ops` language.
+macro_rules! commands {
+    ($($module:ident::$command:ident $(with $shorthands:ident)?),* $(,)?) => {
+        $(
+            pub mod $module;
+            pub use $module::$command;
+        )*
+
+        /// The operations of all commands, as declared by their modules
+        ///
+        /// A new command only adds its module to `commands!`, the command line arguments, the parser, pipeline files and `--ops` are derived from the registry.
+        pub static OPERATIONS: &[&[Operation]] = &[$($module::OPERATIONS),*];
+
+        /// The shorthands of the `--ops` language, as declared by the modules of their commands
+        pub static SHORTHANDS: &[&[Shorthand]] = &[$($($module::$shorthands,)?)*];
+    };
+}
 
 // Include all submodules
-pub mod blur;
-pub mod brighten;
-pub mod combine;
-pub mod contrast;
-pub mod crop;
-pub mod exif;
-pub mod flip;
-pub mod huerotate;
-pub mod invert;
-pub mod resize;
-pub mod resize_filter;
-pub mod rotate;
-pub mod text;
-pub mod unsharpen;
+pub mod registry;
+
+commands! {
+    blur::CmdBlur,
+    brighten::CmdBrighten,
+    combine::CmdCombine with SHORTHANDS,
+    contrast::CmdContrast,
+    crop::CmdCrop with SHORTHANDS,
+    exif::CmdExif,
+    flip::CmdFlip with SHORTHANDS,
+    huerotate::CmdHuerotate with SHORTHANDS,
+    invert::CmdInvert,
+    resize::CmdResize with SHORTHANDS,
+    resize_filter::CmdResizeFilter,
+    rotate::CmdRotate with SHORTHANDS,
+    text::CmdText with SHORTHANDS,
+    unsharpen::CmdUnsharpen,
+}
 
 /// The `Command` trait.
 ///
 /// This trait allows the dynamic implementation of the actual commands which apply modifications to the supplied image(s) by executing the appropriate operations provided by `thumbnailer`.
 /// Passing the image(s) to the execute function should perform the desired modifications to it.
-pub trait Command {
+/// Implementors are `Send` and `Sync`, so the same commands can be shared by all worker threads of a batch.
+pub trait Command: Send + Sync {
     /// Executes the operations of implementors of `Command`, which are provided by `thumbnailer`.
     ///
     /// Returns the modified `GenericThumbnail`
@@ -62,6 +77,181 @@ pub trait Command {
     ///
     /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
     fn print(&self) -> String;
+
+    /// This function returns the paths of all files, which are read by implementors of `Command` while executing, such as the photo of the combine-command.
+    ///
+    /// Changes of these files change the result, even if the values of the command stay the same.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
+    fn dependencies(&self) -> Vec<&Path> {
+        vec![]
+    }
+
+    /// This function returns the structured description of implementors of `Command`, which contains the same values as `print` for machine-readable reports.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
+    fn describe(&self) -> Description;
+
+    /// This function returns the name of the operation of the registry, which creates the same command, and its values in the order of the parameters of the operation.
+    ///
+    /// By default, the operation is the one with the name of the command, whose parameters are taken from the structured description by their names.
+    ///
+    /// Returns the name and the values as text, or `None` if no operation creates the command, such as an exif-command clearing the metadata
+    ///
+    /// # Arguments
+    ///
+    /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        let description = self.describe();
+        let operation = find_operation(description.command)?;
+        let parameters = description.parameters.as_object().cloned().unwrap_or_default();
+        if parameters.keys().any(|key| {
+            !operation
+                .parameters
+                .iter()
+                .any(|parameter| parameter.name == key)
+        }) {
+            return None;
+        }
+
+        let values = operation
+            .parameters
+            .iter()
+            .map(|parameter| match parameters.get(parameter.name) {
+                Some(value) => parameter.kind.text(value),
+                None => parameter.default.map(String::from),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some((operation.name, values))
+    }
+
+    /// This function returns the dimensions of the image after executing implementors of `Command`, without executing them. It is only used to plan `--dry-run`, executed commands measure the image instead.
+    ///
+    /// Returns the new width and height as u32, which are the supplied ones, unless the command changes the dimensions
+    ///
+    /// # Arguments
+    ///
+    /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
+    /// * `width`: The width of the image before executing the command
+    /// * `height`: The height of the image before executing the command
+    fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        (width, height)
+    }
+
+    /// This function checks, whether implementors of `Command` fit into an image of the supplied dimensions, without executing them.
+    ///
+    /// Returns `()`, or the description of the problem, such as a crop box outside of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `&self`: The command containing the `index` as u32 of arguments list (to use the same sequence in ascending order as specified by the user) and all necessary parameters
+    /// * `width`: The width of the image before executing the command
+    /// * `height`: The height of the image before executing the command
+    fn validate(&self, _width: u32, _height: u32) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Representation of the structured description of a command as a struct
+#[derive(Serialize)]
+pub struct Description {
+    /// Contains the `index` as u32 of arguments list
+    pub index: u32,
+    /// Contains the name of the command, such as `resize`
+    pub command: &'static str,
+    /// Contains the parameters of the command as JSON object
+    pub parameters: Value,
+}
+
+/// This function checks, whether an area of the supplied size at the supplied `BoxPosition` lies within the image
+///
+/// The offsets of the position are measured from the corner of the image, which is named by the position.
+///
+/// Returns `()`, or the description of the problem
+///
+/// # Arguments
+///
+/// * `what` - The description of the area, such as `text`
+/// * `position` - The `BoxPosition` of a combine- or text-command
+/// * `size` - The width and height of the area
+/// * `width` - The width of the image
+/// * `height` - The height of the image
+pub(crate) fn validate_position(
+    what: &str,
+    position: &BoxPosition,
+    size: (u32, u32),
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let (x, y) = match *position {
+        BoxPosition::TopLeft(x, y)
+        | BoxPosition::TopRight(x, y)
+        | BoxPosition::BottomLeft(x, y)
+        | BoxPosition::BottomRight(x, y) => (x, y),
+    };
+
+    if x as u64 + size.0 as u64 > width as u64 || y as u64 + size.1 as u64 > height as u64 {
+        return Err(format!(
+            "the {} at {:?} exceeds the image of {}x{}",
+            what, position, width, height
+        ));
+    }
+    Ok(())
+}
+
+/// This function returns the structured description of the supplied `BoxPosition` enum
+///
+/// # Arguments
+///
+/// * `position` - The `BoxPosition` of a combine- or text-command
+pub(crate) fn describe_position(position: &BoxPosition) -> Value {
+    let (anchor, x, y) = match *position {
+        BoxPosition::TopLeft(x, y) => ("top_left", x, y),
+        BoxPosition::TopRight(x, y) => ("top_right", x, y),
+        BoxPosition::BottomLeft(x, y) => ("bottom_left", x, y),
+        BoxPosition::BottomRight(x, y) => ("bottom_right", x, y),
+    };
+    json!({ "anchor": anchor, "x_offset": x, "y_offset": y })
+}
+
+/// This function returns the corner of the supplied `BoxPosition` enum, as used by the names of the operations, and its offsets
+///
+/// # Arguments
+///
+/// * `position` - The `BoxPosition` of a combine- or text-command
+pub(crate) fn position_corner(position: &BoxPosition) -> (&'static str, u32, u32) {
+    match *position {
+        BoxPosition::TopLeft(x, y) => ("tl", x, y),
+        BoxPosition::TopRight(x, y) => ("tr", x, y),
+        BoxPosition::BottomLeft(x, y) => ("bl", x, y),
+        BoxPosition::BottomRight(x, y) => ("br", x, y),
+    }
+}
+
+/// This function returns the name of the supplied operation, whose name consists of the supplied prefix and suffix, such as `combine_` and `tl`
+///
+/// # Arguments
+///
+/// * `operations` - The operations of a command
+/// * `prefix` - The part of the name before the suffix
+/// * `suffix` - The part of the name after the prefix
+pub(crate) fn operation_name(
+    operations: &'static [Operation],
+    prefix: &str,
+    suffix: &str,
+) -> Option<&'static str> {
+    operations
+        .iter()
+        .find(|operation| {
+            operation.name.len() == prefix.len() + suffix.len()
+                && operation.name.starts_with(prefix)
+                && operation.name.ends_with(suffix)
+        })
+        .map(|operation| operation.name)
 }
 
 impl Ord for dyn Command {
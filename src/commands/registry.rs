@@ -0,0 +1,497 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::cli::parser::parse_value;
+use crate::commands::{Command, OPERATIONS, SHORTHANDS};
+use crate::error::{Error, Result};
+
+/// Representation of the type of a parameter of an operation or a preset as an enum
+///
+/// Preset files name the types in snake case, such as `type = "integer"`, and any text as `"string"`.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// A float, parsed as f32
+    Float,
+    /// A signed integer, parsed as i32
+    Integer,
+    /// An unsigned integer, parsed as u32
+    Unsigned,
+    /// A boolean, `true` or `false`
+    Boolean,
+    /// Any text, such as a path
+    #[serde(rename = "string")]
+    Text,
+}
+
+impl Kind {
+    /// This function returns the name of the type, as used by the messages of parse errors
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Kind`-enum
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Float => "f32",
+            Kind::Integer => "i32",
+            Kind::Unsigned => "u32",
+            Kind::Boolean => "bool",
+            Kind::Text => "text",
+        }
+    }
+
+    /// This function checks, whether the type accepts negative values, which start with a hyphen on the command line
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Kind`-enum
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Kind::Float | Kind::Integer)
+    }
+
+    /// This function checks, whether the supplied text can be parsed into the type
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Kind`-enum
+    /// * `value` - The supplied value
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            Kind::Float => value.parse::<f32>().is_ok(),
+            Kind::Integer => value.parse::<i32>().is_ok(),
+            Kind::Unsigned => value.parse::<u32>().is_ok(),
+            Kind::Boolean => value.parse::<bool>().is_ok(),
+            Kind::Text => true,
+        }
+    }
+
+    /// This function converts a value of a pipeline file into the text of a command line value
+    ///
+    /// Returns the text, or `None` if the value has another type, such as a string for a number
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Kind`-enum
+    /// * `value` - The value of a pipeline file, read as JSON value from both formats
+    pub fn text(&self, value: &serde_json::Value) -> Option<String> {
+        match (self, value) {
+            (Kind::Float, serde_json::Value::Number(number)) => Some(number.to_string()),
+            (Kind::Integer, serde_json::Value::Number(number))
+            | (Kind::Unsigned, serde_json::Value::Number(number))
+                if !number.is_f64() =>
+            {
+                Some(number.to_string())
+            }
+            (Kind::Boolean, serde_json::Value::Bool(boolean)) => Some(boolean.to_string()),
+            (Kind::Text, serde_json::Value::String(text)) => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// This function converts the text of a command line value into a value of a pipeline file, the inverse of `text`
+    ///
+    /// Floats are written by the shortest text, which is parsed into the same f32, such as `1.1` instead of `1.100000023841858`.
+    ///
+    /// Returns the value, or `None` if the text can not be parsed into the type
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Kind`-enum
+    /// * `text` - The value as text
+    pub fn value(&self, text: &str) -> Option<serde_json::Value> {
+        match self {
+            Kind::Float => text
+                .parse::<f32>()
+                .ok()
+                .and_then(|float| float.to_string().parse::<f64>().ok())
+                .map(|float| json!(float)),
+            Kind::Integer => text.parse::<i32>().ok().map(|integer| json!(integer)),
+            Kind::Unsigned => text.parse::<u32>().ok().map(|unsigned| json!(unsigned)),
+            Kind::Boolean => text.parse::<bool>().ok().map(|boolean| json!(boolean)),
+            Kind::Text => Some(json!(text)),
+        }
+    }
+}
+
+/// Representation of a parameter of an operation as a struct
+pub struct Parameter {
+    /// Contains the name of the parameter, which is its value name on the command line and its key in pipeline files
+    pub name: &'static str,
+    /// Contains the `Kind` enum as type of the parameter
+    pub kind: Kind,
+    /// Contains the default value of the parameter as text, which may be omitted in pipeline files and `--ops`
+    pub default: Option<&'static str>,
+}
+
+impl Parameter {
+    /// Returns a new required `Parameter` struct with defined:
+    /// * `name`: name of the parameter
+    /// * `kind`: `Kind` enum as type
+    pub const fn new(name: &'static str, kind: Kind) -> Self {
+        Parameter {
+            name,
+            kind,
+            default: None,
+        }
+    }
+
+    /// Returns a new optional `Parameter` struct with defined:
+    /// * `name`: name of the parameter
+    /// * `kind`: `Kind` enum as type
+    /// * `default`: default value as text
+    pub const fn optional(name: &'static str, kind: Kind, default: &'static str) -> Self {
+        Parameter {
+            name,
+            kind,
+            default: Some(default),
+        }
+    }
+}
+
+/// Representation of an operation of the registry as a struct
+///
+/// Each operation is declared once by the module of its command, the command line arguments, the entries of pipeline files and the operations of `--ops` are derived from it.
+pub struct Operation {
+    /// Contains the name of the operation, which is the long command line argument and the `op` of pipeline files, such as `resize_n`
+    pub name: &'static str,
+    /// Contains the parameters of the operation in the order of the command line values
+    pub parameters: &'static [Parameter],
+    /// Contains the help text of the command line argument
+    pub help: &'static str,
+    /// Contains the optional short command line argument, such as `r`
+    pub short: Option<&'static str>,
+    /// Contains the constructor of the command from its parsed values
+    pub create: fn(&Values) -> Result<Box<dyn Command>>,
+}
+
+impl Operation {
+    /// Returns a new `Operation` struct with defined:
+    /// * `name`: name of the command line argument and pipeline entry
+    /// * `parameters`: the parameters in the order of the command line values
+    /// * `help`: help text of the command line argument
+    /// * `create`: constructor of the command
+    pub const fn new(
+        name: &'static str,
+        parameters: &'static [Parameter],
+        help: &'static str,
+        create: fn(&Values) -> Result<Box<dyn Command>>,
+    ) -> Self {
+        Operation {
+            name,
+            parameters,
+            help,
+            short: None,
+            create,
+        }
+    }
+
+    /// Returns the `Operation` struct with the supplied short command line argument
+    pub const fn short(mut self, short: &'static str) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    /// This function creates the command of the operation from the supplied values
+    ///
+    /// Returns a new `Box<dyn Command>`, or an `Error` pointing at `entry` if a value could not be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `&'static self` - the `Operation`-struct
+    /// * `index` - The `index` as u32 of arguments list
+    /// * `entry` - The argument name (or pipeline entry) to report errors
+    /// * `values` - The values as text in the order of the parameters
+    ///
+    /// # Examples
+    /// ```
+    /// # use serde_json::json;
+    /// # use thumbnailer_cli::commands::registry::find_operation;
+    /// let blur = find_operation("blur").unwrap();
+    /// let command = blur.command(2, "blur", vec![String::from("2.5")]).unwrap();
+    /// assert_eq!(command.describe().parameters, json!({ "sigma": 2.5 }));
+    /// ```
+    pub fn command(
+        &'static self,
+        index: u32,
+        entry: &str,
+        values: Vec<String>,
+    ) -> Result<Box<dyn Command>> {
+        (self.create)(&Values {
+            operation: self,
+            index,
+            entry: String::from(entry),
+            values,
+        })
+    }
+}
+
+/// Representation of the values of a single occurrence of an operation as a struct, as passed to its constructor
+pub struct Values {
+    /// Contains the `Operation` struct of the values
+    operation: &'static Operation,
+    /// Contains the `index` as u32 of arguments list
+    index: u32,
+    /// Contains the argument name (or pipeline entry) to report errors
+    entry: String,
+    /// Contains the values as text in the order of the parameters
+    values: Vec<String>,
+}
+
+impl Values {
+    /// This function returns the name of the operation, such as `combine_tl`
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Values`-struct
+    pub fn name(&self) -> &'static str {
+        self.operation.name
+    }
+
+    /// This function returns the `index` as u32 of arguments list
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Values`-struct
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// This function returns the argument name (or pipeline entry) to report errors
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Values`-struct
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    /// This function parses the value of the supplied parameter
+    ///
+    /// Returns the value as `T`, or an `Error::Argument` naming the expected type if it could not be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Values`-struct
+    /// * `name` - The name of the parameter
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<T> {
+        let value = self
+            .operation
+            .parameters
+            .iter()
+            .position(|parameter| parameter.name == name)
+            .and_then(|position| self.values.get(position))
+            .ok_or_else(|| {
+                Error::argument(
+                    &self.entry,
+                    format!("{} requires the value {}", self.name(), name),
+                )
+            })?;
+        parse_value::<T>(&self.entry, name, value)
+    }
+}
+
+/// Result type of the expansion of a shorthand, containing the name of the operation and its values in the order of its parameters
+pub type Expansion = std::result::Result<(&'static str, Vec<String>), Invalid>;
+
+/// Representation of a shorthand of the `--ops` language as a struct, which creates one of several operations of the registry, such as `crop` for `crop_box` and `crop_ratio`
+///
+/// Each shorthand is declared by the module of its command, next to the operations it creates.
+pub struct Shorthand {
+    /// Contains the name of the shorthand, such as `crop`
+    pub name: &'static str,
+    /// Contains the parameters of the shorthand in the order of the positional values, each of them may be omitted
+    pub parameters: &'static [Parameter],
+    /// Contains the usage of the shorthand, as shown by the help of `--ops`
+    pub usage: &'static str,
+    /// Contains the function, which selects the operation and its values from the supplied values
+    pub expand: fn(&Supplied) -> Expansion,
+}
+
+impl Shorthand {
+    /// Returns a new `Shorthand` struct with defined:
+    /// * `name`: name of the shorthand
+    /// * `parameters`: the parameters in the order of the positional values
+    /// * `usage`: usage of the shorthand
+    /// * `expand`: selection of the operation and its values
+    pub const fn new(
+        name: &'static str,
+        parameters: &'static [Parameter],
+        usage: &'static str,
+        expand: fn(&Supplied) -> Expansion,
+    ) -> Self {
+        Shorthand {
+            name,
+            parameters,
+            usage,
+            expand,
+        }
+    }
+
+    /// This function selects the operation of the registry and its values from the supplied values
+    ///
+    /// The values are checked against the types of the parameters by the caller.
+    ///
+    /// Returns the name of the operation and its values in the order of its parameters, or an `Invalid` struct describing the problem
+    ///
+    /// # Arguments
+    ///
+    /// * `&'static self` - the `Shorthand`-struct
+    /// * `values` - The supplied values in the order of the parameters, `None` for omitted ones
+    ///
+    /// # Examples
+    /// ```
+    /// # use thumbnailer_cli::commands::registry::find_shorthand;
+    /// let rotate = find_shorthand("rotate").unwrap();
+    /// assert_eq!(rotate.operation(vec![Some("180")]).ok(), Some(("rotate180", vec![])));
+    /// ```
+    pub fn operation(&'static self, values: Vec<Option<&str>>) -> Expansion {
+        (self.expand)(&Supplied {
+            shorthand: self,
+            values,
+        })
+    }
+}
+
+/// Representation of the supplied values of a shorthand as a struct, as passed to its expansion
+pub struct Supplied<'a> {
+    /// Contains the `Shorthand` struct of the values
+    shorthand: &'static Shorthand,
+    /// Contains the values in the order of the parameters, `None` for omitted ones
+    values: Vec<Option<&'a str>>,
+}
+
+impl<'a> Supplied<'a> {
+    /// This function returns the value of the supplied parameter, or `None` if it was omitted
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Supplied`-struct
+    /// * `name` - The name of the parameter
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.shorthand
+            .parameters
+            .iter()
+            .position(|parameter| parameter.name == name)
+            .and_then(|position| self.values.get(position).copied().flatten())
+    }
+
+    /// This function returns the value of the supplied required parameter
+    ///
+    /// Returns the value, or an `Invalid` struct if it was omitted
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Supplied`-struct
+    /// * `name` - The name of the parameter
+    pub fn required(&self, name: &str) -> std::result::Result<&'a str, Invalid> {
+        self.get(name).ok_or_else(|| self.missing(name))
+    }
+
+    /// This function returns an `Invalid` struct for a required value, which was omitted
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Supplied`-struct
+    /// * `expected` - The description of the omitted value, such as `x or ratio`
+    pub fn missing(&self, expected: &str) -> Invalid {
+        Invalid {
+            parameter: None,
+            message: format!("{} requires the value {}", self.shorthand.name, expected),
+        }
+    }
+
+    /// This function returns an `Invalid` struct for the value of the supplied parameter, which does not match the expectation
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Supplied`-struct
+    /// * `name` - The name of the parameter
+    /// * `expected` - The description of the expected value
+    pub fn invalid(&self, name: &'static str, expected: &str) -> Invalid {
+        Invalid {
+            parameter: Some(name),
+            message: format!(
+                "expected {}, got {}",
+                expected,
+                self.get(name).unwrap_or_default()
+            ),
+        }
+    }
+
+    /// This function splits the value of the supplied parameter into two values of the supplied type, such as `4:3` or `600x450`
+    ///
+    /// Returns both values as text, or an `Invalid` struct if the value could not be split or parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Supplied`-struct
+    /// * `name` - The name of the parameter
+    /// * `separator` - The character between both values
+    /// * `kind` - The `Kind` enum as type of both values
+    /// * `expected` - The description of the expected format to report errors
+    pub fn pair(
+        &self,
+        name: &'static str,
+        separator: char,
+        kind: Kind,
+        expected: &str,
+    ) -> std::result::Result<(String, String), Invalid> {
+        let (first, second) = self
+            .required(name)?
+            .split_once(separator)
+            .ok_or_else(|| self.invalid(name, expected))?;
+        match kind.accepts(first) && kind.accepts(second) {
+            true => Ok((String::from(first), String::from(second))),
+            false => Err(self.invalid(name, &format!("{} as {}", expected, kind.name()))),
+        }
+    }
+}
+
+/// Representation of a problem with the supplied values of a shorthand as a struct
+pub struct Invalid {
+    /// Contains the name of the parameter, whose value is invalid, or `None` if the problem concerns the whole shorthand
+    pub parameter: Option<&'static str>,
+    /// Contains the description of the problem
+    pub message: String,
+}
+
+/// This function returns all operations of the registry in the order of their commands
+///
+/// # Examples
+/// ```
+/// # use thumbnailer_cli::commands::registry::operations;
+/// assert!(operations().any(|operation| operation.name == "resize_n"));
+/// ```
+pub fn operations() -> impl Iterator<Item = &'static Operation> {
+    OPERATIONS.iter().flat_map(|operations| operations.iter())
+}
+
+/// This function returns the operation with the supplied name
+///
+/// # Arguments
+///
+/// * `name` - The name of the operation, such as `blur`
+pub fn find_operation(name: &str) -> Option<&'static Operation> {
+    operations().find(|operation| operation.name == name)
+}
+
+/// This function returns the names of all operations, to report errors
+pub fn operation_names() -> Vec<&'static str> {
+    operations().map(|operation| operation.name).collect()
+}
+
+/// This function returns all shorthands of the `--ops` language in the order of their commands
+pub fn shorthands() -> impl Iterator<Item = &'static Shorthand> {
+    SHORTHANDS.iter().flat_map(|shorthands| shorthands.iter())
+}
+
+/// This function returns the shorthand of the `--ops` language with the supplied name
+///
+/// # Arguments
+///
+/// * `name` - The name of the shorthand, such as `crop`
+pub fn find_shorthand(name: &str) -> Option<&'static Shorthand> {
+    shorthands().find(|shorthand| shorthand.name == name)
+}
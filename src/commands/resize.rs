@@ -1,6 +1,11 @@
+use serde_json::{json, Value};
 use thumbnailer::{GenericThumbnail, Resize};
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Invalid, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the resize-command as a struct
 pub struct CmdResize {
@@ -19,6 +24,37 @@ impl CmdResize {
     }
 }
 
+/// The parameters of the resize- and resize_filter-commands, all dimensions are optional in pipeline files
+pub(crate) const PARAMETERS: &[Parameter] = &[
+    Parameter::optional("nwidth", Kind::Unsigned, "0"),
+    Parameter::optional("nheight", Kind::Unsigned, "0"),
+    Parameter::optional("exact", Kind::Boolean, "false"),
+];
+
+/// The operations of the resize-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "resize",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+];
+
+/// The shorthands of the resize- and resize_filter-commands in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "resize",
+    &[
+        Parameter::new("bbox", Kind::Text),
+        Parameter::new("width", Kind::Unsigned),
+        Parameter::new("height", Kind::Unsigned),
+        Parameter::new("exact", Kind::Text),
+        Parameter::new("filter", Kind::Text),
+    ],
+    "resize:bbox=WxH|exact=WxH|width=W|height=H with an optional filter (nearest, triangle, catmullrom, gaussian, lanczos3)",
+    expand,
+)];
+
 impl Command for CmdResize {
     /// This function calls the actual resize command, depending on the values given by the members of `CmdResize`-struct.
     ///
@@ -40,6 +76,8 @@ impl Command for CmdResize {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Resize;
+    /// # use thumbnailer_cli::commands::{CmdResize, Command};
     /// let index = 10;
     /// let resize = CmdResize::new(index, Resize::BoundingBox(400, 300));
     /// println!("index = {}", resize.get_index());
@@ -57,10 +95,200 @@ impl Command for CmdResize {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Resize;
+    /// # use thumbnailer_cli::commands::{CmdResize, Command};
     /// let resize = CmdResize::new(10, Resize::BoundingBox(400, 300));
     /// println!("{}", resize.print());
     /// ```
     fn print(&self) -> String {
         format!("► {:02}. resize:\t\t{:?}", self.index, self.size)
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdResize`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResize`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "resize",
+            parameters: describe_size(&self.size),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdResize`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResize`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        Some(("resize", size_values(&self.size)))
+    }
+
+    /// This function returns the dimensions of the image after executing the command, depending on the values given by the members of `CmdResize`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResize`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        resize_dimensions(width, height, self.size)
+    }
+}
+
+/// This function returns the structured description of the supplied `Resize` enum
+///
+/// # Arguments
+///
+/// * `size` - The `Resize` of a resize-command
+pub(crate) fn describe_size(size: &Resize) -> Value {
+    match *size {
+        Resize::Width(width) => json!({ "mode": "width", "width": width }),
+        Resize::Height(height) => json!({ "mode": "height", "height": height }),
+        Resize::BoundingBox(width, height) => {
+            json!({ "mode": "bounding_box", "width": width, "height": height })
+        }
+        Resize::ExactBox(width, height) => {
+            json!({ "mode": "exact_box", "width": width, "height": height })
+        }
+    }
+}
+
+/// This function returns the values of the parameters of the resize- and resize_filter-operations for the supplied `Resize` enum
+///
+/// # Arguments
+///
+/// * `size` - The `Resize` enum of a resize- or resize_filter-command
+pub(crate) fn size_values(size: &Resize) -> Vec<String> {
+    let (width, height, exact) = match *size {
+        Resize::Width(width) => (width, 0, false),
+        Resize::Height(height) => (0, height, false),
+        Resize::BoundingBox(width, height) => (width, height, false),
+        Resize::ExactBox(width, height) => (width, height, true),
+    };
+    vec![width.to_string(), height.to_string(), exact.to_string()]
+}
+
+/// This function returns the dimensions of an image after resizing it
+///
+/// The aspect ratio is preserved, unless the image is resized to an exact box.
+///
+/// Returns the new width and height as u32
+///
+/// # Arguments
+///
+/// * `width` - The width of the image before resizing
+/// * `height` - The height of the image before resizing
+/// * `size` - The `Resize` enum of the resize-command
+///
+/// # Examples
+/// The example is not compiled, as `resize_dimensions` is only visible in the crate.
+///
+/// ```ignore
+/// assert_eq!(resize_dimensions(1600, 1200, Resize::Width(800)), (800, 600));
+/// assert_eq!(resize_dimensions(1600, 1200, Resize::BoundingBox(400, 400)), (400, 300));
+/// ```
+pub(crate) fn resize_dimensions(width: u32, height: u32, size: Resize) -> (u32, u32) {
+    let (box_width, box_height) = match size {
+        Resize::ExactBox(width, height) => return (width, height),
+        Resize::Width(width) => (width, u32::MAX),
+        Resize::Height(height) => (u32::MAX, height),
+        Resize::BoundingBox(width, height) => (width, height),
+    };
+
+    let ratio = f64::min(
+        box_width as f64 / width.max(1) as f64,
+        box_height as f64 / height.max(1) as f64,
+    );
+    let scale = |length: u32| ((length as f64 * ratio).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// This function selects the resize-operation, or the resize_filter-operation of the supplied filter
+///
+/// Returns the name of the operation and its values, or an `Invalid` struct if not exactly one size is supplied or the filter is unknown
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    let sizes = [
+        values.get("bbox"),
+        values.get("width"),
+        values.get("height"),
+        values.get("exact"),
+    ];
+    let size = match sizes {
+        [Some(_), None, None, None] => {
+            let (width, height) = values.pair("bbox", 'x', Kind::Unsigned, "WIDTHxHEIGHT")?;
+            [width, height, String::from("false")]
+        }
+        [None, Some(width), None, None] => [
+            String::from(width),
+            String::from("0"),
+            String::from("false"),
+        ],
+        [None, None, Some(height), None] => [
+            String::from("0"),
+            String::from(height),
+            String::from("false"),
+        ],
+        [None, None, None, Some(_)] => {
+            let (width, height) = values.pair("exact", 'x', Kind::Unsigned, "WIDTHxHEIGHT")?;
+            [width, height, String::from("true")]
+        }
+        _ => {
+            return Err(Invalid {
+                parameter: None,
+                message: match sizes.iter().all(Option::is_none) {
+                    true => String::from("resize expects one of bbox, exact, width or height"),
+                    false => {
+                        String::from("resize expects only one of bbox, exact, width or height")
+                    }
+                },
+            })
+        }
+    };
+
+    let name = match values.get("filter") {
+        None => "resize",
+        Some("nearest") => "resize_n",
+        Some("triangle") => "resize_t",
+        Some("catmullrom") => "resize_c",
+        Some("gaussian") => "resize_g",
+        Some("lanczos3") => "resize_l",
+        Some(_) => {
+            return Err(values.invalid(
+                "filter",
+                "one of nearest, triangle, catmullrom, gaussian, lanczos3",
+            ))
+        }
+    };
+    Ok((name, size.to_vec()))
+}
+
+/// This function creates the resize-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let width = values.get::<u32>("nwidth")?;
+    let height = values.get::<u32>("nheight")?;
+
+    let size = if values.get::<bool>("exact")? {
+        Resize::ExactBox(width, height)
+    } else if height == 0 {
+        Resize::Width(width)
+    } else if width == 0 {
+        Resize::Height(height)
+    } else {
+        Resize::BoundingBox(width, height)
+    };
+
+    Ok(Box::new(CmdResize::new(values.index(), size)))
 }
@@ -1,6 +1,10 @@
+use serde_json::json;
 use thumbnailer::{GenericThumbnail, ResampleFilter, Resize};
 
-use crate::commands::Command;
+use crate::commands::registry::{Operation, Values};
+use crate::commands::resize::{describe_size, resize_dimensions, size_values, PARAMETERS};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the resizeFilter-command as a struct
 pub struct CmdResizeFilter {
@@ -26,6 +30,40 @@ impl CmdResizeFilter {
     }
 }
 
+/// The operations of the resize_filter-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "resize_n",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Nearest is the used filter (Nearest Neighbor Filter). To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+    Operation::new(
+        "resize_t",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Triangle is the used filter (Linear Filter). To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+    Operation::new(
+        "resize_c",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. CatmullRom is the used filter (Cubic Filter). To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+    Operation::new(
+        "resize_g",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Gaussian is the used filter (Gaussian Filter). To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+    Operation::new(
+        "resize_l",
+        PARAMETERS,
+        "Resize the supplied image(s) to the specified dimensions. nwidth as u32 and nheight as u32 are the new dimensions. exact as bool forces the exact resizing, but the aspect ratio may change. Lanczos3 is the used filter (Lanczos with window 3). To resize only by one dimension, set the other to 0.",
+        create,
+    ),
+];
+
 impl Command for CmdResizeFilter {
     /// This function calls the actual resize_filter command, depending on the values given by the members of `CmdResizeFilter`-struct.
     ///
@@ -47,6 +85,8 @@ impl Command for CmdResizeFilter {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::{ResampleFilter, Resize};
+    /// # use thumbnailer_cli::commands::{CmdResizeFilter, Command};
     /// let index = 11;
     /// let resize_filter = CmdResizeFilter::new(index, Resize::BoundingBox(400, 300), ResampleFilter::Nearest);
     /// println!("index = {}", resize_filter.get_index());
@@ -64,6 +104,8 @@ impl Command for CmdResizeFilter {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::{ResampleFilter, Resize};
+    /// # use thumbnailer_cli::commands::{CmdResizeFilter, Command};
     /// let resize_filter = CmdResizeFilter::new(11, Resize::BoundingBox(400, 300), ResampleFilter::Nearest);
     /// println!("{}", resize_filter.print());
     /// ```
@@ -73,4 +115,79 @@ impl Command for CmdResizeFilter {
             self.index, self.size, self.filter
         )
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdResizeFilter`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResizeFilter`-struct
+    fn describe(&self) -> Description {
+        let mut parameters = describe_size(&self.size);
+        parameters["filter"] = json!(format!("{:?}", self.filter).to_lowercase());
+
+        Description {
+            index: self.index,
+            command: "resize_filter",
+            parameters,
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdResizeFilter`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResizeFilter`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        let name = match self.filter {
+            ResampleFilter::Nearest => "resize_n",
+            ResampleFilter::Triangle => "resize_t",
+            ResampleFilter::CatmullRom => "resize_c",
+            ResampleFilter::Gaussian => "resize_g",
+            ResampleFilter::Lanczos3 => "resize_l",
+        };
+        Some((name, size_values(&self.size)))
+    }
+
+    /// This function returns the dimensions of the image after executing the command, depending on the values given by the members of `CmdResizeFilter`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdResizeFilter`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        resize_dimensions(width, height, self.size)
+    }
+}
+
+/// This function creates the resize_filter-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let width = values.get::<u32>("nwidth")?;
+    let height = values.get::<u32>("nheight")?;
+
+    let size = if height == 0 {
+        Resize::Width(width)
+    } else if width == 0 {
+        Resize::Height(height)
+    } else if !values.get::<bool>("exact")? {
+        Resize::BoundingBox(width, height)
+    } else {
+        Resize::ExactBox(width, height)
+    };
+
+    let filter = match values.name() {
+        "resize_t" => ResampleFilter::Triangle,
+        "resize_c" => ResampleFilter::CatmullRom,
+        "resize_g" => ResampleFilter::Gaussian,
+        "resize_l" => ResampleFilter::Lanczos3,
+        _ => ResampleFilter::Nearest,
+    };
+
+    Ok(Box::new(CmdResizeFilter::new(values.index(), size, filter)))
 }
@@ -1,6 +1,11 @@
+use serde_json::json;
 use thumbnailer::{GenericThumbnail, Rotation};
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{operation_name, Command, Description};
+use crate::error::Result;
 
 /// Representation of the rotate-command as a struct
 pub struct CmdRotate {
@@ -19,6 +24,37 @@ impl CmdRotate {
     }
 }
 
+/// The operations of the rotate-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "rotate90",
+        &[],
+        "Rotate the supplied image(s) 90 degrees clockwise.",
+        create,
+    )
+    .short("r"),
+    Operation::new(
+        "rotate180",
+        &[],
+        "Rotate the supplied image(s) 180 degrees clockwise.",
+        create,
+    ),
+    Operation::new(
+        "rotate270",
+        &[],
+        "Rotate the supplied image(s) 270 degrees clockwise.",
+        create,
+    ),
+];
+
+/// The shorthands of the rotate-command in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "rotate",
+    &[Parameter::new("degree", Kind::Text)],
+    "rotate:90|180|270",
+    expand,
+)];
+
 impl Command for CmdRotate {
     /// This function calls the actual rotate command, depending on the value given by the `CmdRotate`-struct.
     ///
@@ -40,6 +76,8 @@ impl Command for CmdRotate {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Rotation;
+    /// # use thumbnailer_cli::commands::{CmdRotate, Command};
     /// let index = 12;
     /// let rotate = CmdRotate::new(index, Rotation::Rotate90);
     /// println!("index = {}", rotate.get_index());
@@ -57,6 +95,8 @@ impl Command for CmdRotate {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::Rotation;
+    /// # use thumbnailer_cli::commands::{CmdRotate, Command};
     /// let rotate = CmdRotate::new(12, Rotation::Rotate90);
     /// println!("{}", rotate.print());
     /// ```
@@ -66,4 +106,75 @@ impl Command for CmdRotate {
             self.index, self.rotation
         )
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdRotate`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdRotate`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "rotate",
+            parameters: json!({ "rotation": format!("{:?}", self.rotation).to_lowercase() }),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdRotate`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdRotate`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        let degree = match self.rotation {
+            Rotation::Rotate90 => "90",
+            Rotation::Rotate180 => "180",
+            Rotation::Rotate270 => "270",
+        };
+        Some((operation_name(OPERATIONS, "rotate", degree)?, vec![]))
+    }
+
+    /// This function returns the dimensions of the image after executing the command, depending on the values given by the members of `CmdRotate`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdRotate`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        match self.rotation {
+            Rotation::Rotate180 => (width, height),
+            Rotation::Rotate90 | Rotation::Rotate270 => (height, width),
+        }
+    }
+}
+
+/// This function selects the rotate-operation of the supplied degree
+///
+/// Returns the name of the operation, or an `Invalid` struct if the degree is omitted or unknown
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    let name = operation_name(OPERATIONS, "rotate", values.required("degree")?)
+        .ok_or_else(|| values.invalid("degree", "90, 180 or 270"))?;
+    Ok((name, vec![]))
+}
+
+/// This function creates the rotate-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let rotation = match values.name() {
+        "rotate180" => Rotation::Rotate180,
+        "rotate270" => Rotation::Rotate270,
+        _ => Rotation::Rotate90,
+    };
+
+    Ok(Box::new(CmdRotate::new(values.index(), rotation)))
 }
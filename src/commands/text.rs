@@ -1,6 +1,13 @@
+use serde_json::json;
 use thumbnailer::{BoxPosition, GenericThumbnail};
 
-use crate::commands::Command;
+use crate::commands::registry::{
+    Expansion, Kind, Operation, Parameter, Shorthand, Supplied, Values,
+};
+use crate::commands::{
+    describe_position, operation_name, position_corner, validate_position, Command, Description,
+};
+use crate::error::Result;
 
 /// Representation of the text-command as a struct
 pub struct CmdText {
@@ -26,6 +33,54 @@ impl CmdText {
     }
 }
 
+/// The parameters of the text-command, shared by all positions
+const PARAMETERS: &[Parameter] = &[
+    Parameter::new("text", Kind::Text),
+    Parameter::new("x_offset", Kind::Unsigned),
+    Parameter::new("y_offset", Kind::Unsigned),
+];
+
+/// The operations of the text-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "text_tl",
+        PARAMETERS,
+        "Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP LEFT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "text_tr",
+        PARAMETERS,
+        "Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the TOP RIGHT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "text_bl",
+        PARAMETERS,
+        "Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM LEFT corner of the photo.",
+        create,
+    ),
+    Operation::new(
+        "text_br",
+        PARAMETERS,
+        "Inserts a text as String into the supplied image(s). x_offset as u32 is the horizontal and y-offset as u32 vertical offset to the BOTTOM RIGHT corner of the photo.",
+        create,
+    ),
+];
+
+/// The shorthands of the text-command in the `--ops` language
+pub const SHORTHANDS: &[Shorthand] = &[Shorthand::new(
+    "text",
+    &[
+        Parameter::new("pos", Kind::Text),
+        Parameter::new("x", Kind::Unsigned),
+        Parameter::new("y", Kind::Unsigned),
+        Parameter::new("text", Kind::Text),
+    ],
+    "text:pos,x,y,text with pos tl, tr, bl or br",
+    expand,
+)];
+
 impl Command for CmdText {
     /// This function calls the actual text command, depending on the values given by the members of `CmdText`-struct.
     ///
@@ -47,6 +102,8 @@ impl Command for CmdText {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::BoxPosition;
+    /// # use thumbnailer_cli::commands::{CmdText, Command};
     /// let index = 13;
     /// let text = CmdText::new(index, String::from("(c) Thumbnailer"), BoxPosition::TopLeft(37, 28));
     /// println!("index = {}", text.get_index());
@@ -64,6 +121,8 @@ impl Command for CmdText {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer::BoxPosition;
+    /// # use thumbnailer_cli::commands::{CmdText, Command};
     /// let text = CmdText::new(13, String::from("(c) Thumbnailer"), BoxPosition::TopLeft(37, 28));
     /// println!("{}", text.print());
     /// ```
@@ -73,4 +132,93 @@ impl Command for CmdText {
             self.index, self.position, self.text
         )
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdText`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdText`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "text",
+            parameters: json!({ "text": self.text, "position": describe_position(&self.position) }),
+        }
+    }
+
+    /// This function returns the name of the operation, which creates the same command, and its values, depending on the values given by the members of `CmdText`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdText`-struct
+    fn operation(&self) -> Option<(&'static str, Vec<String>)> {
+        let (corner, x_offset, y_offset) = position_corner(&self.position);
+        let name = operation_name(OPERATIONS, "text_", corner)?;
+        Some((
+            name,
+            vec![
+                self.text.clone(),
+                x_offset.to_string(),
+                y_offset.to_string(),
+            ],
+        ))
+    }
+
+    /// This function checks, whether the position of the text lies within an image of the supplied dimensions.
+    ///
+    /// The size of the rendered text is not known in advance, so only its anchor, the first pixel at the offsets, is checked, as documented by `--dry-run`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdText`-struct
+    /// * `width` - The width of the image before executing the command
+    /// * `height` - The height of the image before executing the command
+    fn validate(&self, width: u32, height: u32) -> std::result::Result<(), String> {
+        validate_position("text", &self.position, (1, 1), width, height)
+    }
+}
+
+/// This function selects the text-operation of the supplied corner
+///
+/// Returns the name of the operation and its values, or an `Invalid` struct if a value is omitted or the corner is unknown
+///
+/// # Arguments
+///
+/// * `values` - The `Supplied` struct of the shorthand
+fn expand(values: &Supplied) -> Expansion {
+    let name = operation_name(OPERATIONS, "text_", values.required("pos")?)
+        .ok_or_else(|| values.invalid("pos", "one of tl, tr, bl, br"))?;
+    let x_offset = values.required("x")?;
+    let y_offset = values.required("y")?;
+    let text = values.required("text")?;
+    Ok((
+        name,
+        vec![
+            String::from(text),
+            String::from(x_offset),
+            String::from(y_offset),
+        ],
+    ))
+}
+
+/// This function creates the text-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    let text = values.get::<String>("text")?;
+    let x_offset = values.get::<u32>("x_offset")?;
+    let y_offset = values.get::<u32>("y_offset")?;
+
+    let position = match values.name() {
+        "text_tr" => BoxPosition::TopRight(x_offset, y_offset),
+        "text_bl" => BoxPosition::BottomLeft(x_offset, y_offset),
+        "text_br" => BoxPosition::BottomRight(x_offset, y_offset),
+        _ => BoxPosition::TopLeft(x_offset, y_offset),
+    };
+
+    Ok(Box::new(CmdText::new(values.index(), text, position)))
 }
@@ -1,6 +1,9 @@
+use serde_json::json;
 use thumbnailer::GenericThumbnail;
 
-use crate::commands::Command;
+use crate::commands::registry::{Kind, Operation, Parameter, Values};
+use crate::commands::{Command, Description};
+use crate::error::Result;
 
 /// Representation of the unsharpen-command as a struct
 pub struct CmdUnsharpen {
@@ -26,6 +29,19 @@ impl CmdUnsharpen {
     }
 }
 
+/// The operations of the unsharpen-command
+pub const OPERATIONS: &[Operation] = &[
+    Operation::new(
+        "unsharpen",
+        &[
+            Parameter::new("sigma", Kind::Float),
+            Parameter::new("threshold", Kind::Integer),
+        ],
+        "Performs an unsharpen mask on the supplied image(s). sigma as f32 is the amount to unsharpen the image by. threshold as i32 controls the minimal brightness change or how far apart adjacent tonal values have to be before the filter does anything.",
+        create,
+    ),
+];
+
 impl Command for CmdUnsharpen {
     /// This function calls the actual unsharpen command, depending on the values given by the members of `CmdUnsharpen`-struct.
     ///
@@ -47,6 +63,7 @@ impl Command for CmdUnsharpen {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdUnsharpen, Command};
     /// let index = 14;
     /// let unsharpen = CmdUnsharpen::new(index, 0.3, 2);
     /// println!("index = {}", unsharpen.get_index());
@@ -64,6 +81,7 @@ impl Command for CmdUnsharpen {
     ///
     /// # Examples
     /// ```
+    /// # use thumbnailer_cli::commands::{CmdUnsharpen, Command};
     /// let unsharpen = CmdUnsharpen::new(14, 0.3, 2);
     /// println!("{}", unsharpen.print());
     /// ```
@@ -73,4 +91,32 @@ impl Command for CmdUnsharpen {
             self.index, self.sigma, self.threshold
         )
     }
+
+    /// This function returns the structured description, depending on the `index` and the values given by the members of `CmdUnsharpen`-struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `CmdUnsharpen`-struct
+    fn describe(&self) -> Description {
+        Description {
+            index: self.index,
+            command: "unsharpen",
+            parameters: json!({ "sigma": self.sigma, "threshold": self.threshold }),
+        }
+    }
+}
+
+/// This function creates the unsharpen-command from the supplied values
+///
+/// Returns a new `Box<dyn Command>`, or an `Error::Argument` if a value could not be parsed
+///
+/// # Arguments
+///
+/// * `values` - The `Values` struct of the occurrence
+fn create(values: &Values) -> Result<Box<dyn Command>> {
+    Ok(Box::new(CmdUnsharpen::new(
+        values.index(),
+        values.get("sigma")?,
+        values.get("threshold")?,
+    )))
 }
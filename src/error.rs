@@ -0,0 +1,145 @@
+use std::fmt;
+use std::io;
+
+/// Representation of all errors of the thumbnailer command line interface as an enum
+///
+/// Each variant is mapped to its own exit code by `exit_code`:
+/// * `2` - `Argument`: the value(s) of an argument could not be parsed
+/// * `3` - `Load`: an image could not be loaded
+/// * `4` - `Store`: the image could not be stored
+/// * `5` - `UnsupportedFormat`: a file format is not supported
+/// * `6` - `Io`: reading or writing a file failed
+/// * `7` - `Batch`: some images of a batch could not be processed, their errors are printed while processing
+/// * `8` - `Budget`: the image does not fit into the maximum file size, not even with the lowest quality
+/// * `9` - `Bounds`: the commands do not fit the dimensions of the image, as found by `--dry-run`
+///
+/// Errors of clap itself, such as an unknown argument, keep the exit code `1` of clap.
+#[derive(Debug)]
+pub enum Error {
+    /// The value(s) of the argument `arg` could not be parsed, described by `message`
+    Argument { arg: String, message: String },
+    /// The image with the supplied `path` could not be loaded for the argument `arg`
+    Load { arg: String, path: String },
+    /// The image could not be stored to the supplied `path` for the argument `arg`
+    Store { arg: String, path: String },
+    /// The `format` supplied for the argument `arg` is not supported
+    UnsupportedFormat { arg: String, format: String },
+    /// Reading or writing the file with the supplied `path` failed for the argument `arg`
+    Io {
+        arg: String,
+        path: String,
+        source: io::Error,
+    },
+    /// `failed` of `total` images of a batch could not be processed
+    Batch { failed: usize, total: usize },
+    /// The image stored to `path` has `size` bytes with the lowest quality, which exceeds `max_bytes` of the argument `arg`
+    Budget {
+        arg: String,
+        path: String,
+        max_bytes: u64,
+        size: u64,
+    },
+    /// The commands do not fit the dimensions of the image with the supplied `path`, described by `problems`, as found by the argument `arg`
+    Bounds {
+        arg: String,
+        path: String,
+        problems: Vec<String>,
+    },
+}
+
+/// Result type of the thumbnailer command line interface, using `Error` as error
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// This function returns a new `Error::Argument` for the argument `arg`
+    ///
+    /// # Arguments
+    ///
+    /// * `arg` - The argument name (or pipeline entry) the error occurred in
+    /// * `message` - The description of the error
+    pub fn argument(arg: &str, message: String) -> Self {
+        Error::Argument {
+            arg: String::from(arg),
+            message,
+        }
+    }
+
+    /// This function returns the documented exit code of the error as i32
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - the `Error`-enum
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Argument { .. } => 2,
+            Error::Load { .. } => 3,
+            Error::Store { .. } => 4,
+            Error::UnsupportedFormat { .. } => 5,
+            Error::Io { .. } => 6,
+            Error::Batch { .. } => 7,
+            Error::Budget { .. } => 8,
+            Error::Bounds { .. } => 9,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    /// This method formats the error as plain message, starting with the argument the error occurred in
+    ///
+    /// The command line interface decorates the message, before it is printed to the user.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Argument { arg, message } => write!(f, "{}: {}", arg, message),
+            Error::Load { arg, path } => write!(
+                f,
+                "{}: failed to load the image with the supplied path {}",
+                arg, path
+            ),
+            Error::Store { arg, path } => write!(
+                f,
+                "{}: failed to store the image to the supplied path {}",
+                arg, path
+            ),
+            Error::UnsupportedFormat { arg, format } => {
+                write!(f, "{}: the format {} is not supported", arg, format)
+            }
+            Error::Io { arg, path, source } => write!(f, "{}: {}: {}", arg, path, source),
+            Error::Batch { failed, total } => write!(
+                f,
+                "batch: {} of {} images could not be processed",
+                failed, total
+            ),
+            Error::Budget {
+                arg,
+                path,
+                max_bytes,
+                size,
+            } => write!(
+                f,
+                "{}: the image {} has {} bytes with the lowest quality, which exceeds the maximum of {} bytes",
+                arg, path, size, max_bytes
+            ),
+            Error::Bounds {
+                arg,
+                path,
+                problems,
+            } => write!(
+                f,
+                "{}: the commands do not fit the image {}: {}",
+                arg,
+                path,
+                problems.join("; ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    /// This method returns the underlying `io::Error` of `Error::Io`
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,546 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ColorType, DynamicImage, GenericImageView};
+use thumbnailer::target::TargetFormat;
+
+use crate::cli::{ARG_MAX_BYTES, NAME_FILE_OUT};
+use crate::error::{Error, Result};
+
+/// The names of all supported output formats, including their aliases, as shown in the help
+pub const FORMATS: [&str; 10] = [
+    "png", "jpg", "jpeg", "jpe", "jfif", "tif", "tiff", "bmp", "dib", "gif",
+];
+
+/// This function returns the `TargetFormat` of the supplied format name or file extension
+///
+/// The name is matched case-insensitive, so `JPG`, `jpeg` and `Jpe` are all recognised as `TargetFormat::Jpeg`.
+///
+/// Returns the `TargetFormat`, or an `Error::UnsupportedFormat` if the name is unknown
+///
+/// # Arguments
+///
+/// * `arg` - The argument name the format was supplied for, to report errors
+/// * `name` - The format name or file extension without leading dot
+///
+/// # Examples
+/// ```
+/// # use thumbnailer::target::TargetFormat;
+/// # use thumbnailer_cli::cli::NAME_FILE_OUT;
+/// # use thumbnailer_cli::format::target_format;
+/// assert!(matches!(target_format("format", "JPG"), Ok(TargetFormat::Jpeg)));
+/// assert!(target_format(NAME_FILE_OUT, "pgn").is_err());
+/// ```
+pub fn target_format(arg: &str, name: &str) -> Result<TargetFormat> {
+    match name.to_lowercase().as_str() {
+        "png" => Ok(TargetFormat::Png),
+        "jpg" | "jpeg" | "jpe" | "jfif" => Ok(TargetFormat::Jpeg),
+        "tif" | "tiff" => Ok(TargetFormat::Tiff),
+        "bmp" | "dib" => Ok(TargetFormat::Bmp),
+        "gif" => Ok(TargetFormat::Gif),
+        _ => Err(Error::UnsupportedFormat {
+            arg: String::from(arg),
+            format: String::from(name),
+        }),
+    }
+}
+
+/// This function returns the name of the supplied `TargetFormat`, as used by `--format`
+///
+/// # Arguments
+///
+/// * `format` - The `TargetFormat`
+pub fn format_name(format: &TargetFormat) -> &'static str {
+    match format {
+        TargetFormat::Png => "png",
+        TargetFormat::Jpeg => "jpeg",
+        TargetFormat::Tiff => "tiff",
+        TargetFormat::Bmp => "bmp",
+        TargetFormat::Gif => "gif",
+    }
+}
+
+/// This function returns the `TargetFormat` to store the image to the supplied output path
+///
+/// The format is taken from `format`, if it is supplied, otherwise it is detected by the extension of `output`.
+///
+/// Returns the `TargetFormat`, or an `Error` if the format is unknown or the output path has no extension
+///
+/// # Arguments
+///
+/// * `output` - The (resolved) output path
+/// * `format` - The format name supplied by `--format` or a pipeline output, overriding the detection
+pub fn output_format(output: &Path, format: Option<&str>) -> Result<TargetFormat> {
+    if let Some(format) = format {
+        return target_format(NAME_FILE_OUT, format);
+    }
+
+    match output.extension().and_then(OsStr::to_str) {
+        Some(extension) => target_format(NAME_FILE_OUT, extension),
+        None => Err(Error::argument(
+            NAME_FILE_OUT,
+            format!(
+                "the output path {} has no extension to detect the format, use --format to choose it",
+                output.display()
+            ),
+        )),
+    }
+}
+
+/// The names of all PNG compression levels
+pub const PNG_COMPRESSIONS: [&str; 5] = ["fast", "default", "best", "huffman", "rle"];
+
+/// The names of all PNG filter types
+pub const PNG_FILTERS: [&str; 5] = ["none", "sub", "up", "avg", "paeth"];
+
+/// Default quality of the JPEG encoder, if only PNG settings are supplied
+const DEFAULT_QUALITY: u8 = 75;
+
+/// Representation of the settings to encode the output image as a struct
+///
+/// All settings are optional, unset settings keep the defaults of the encoder.
+#[derive(Clone, Debug, Default)]
+pub struct Encoding {
+    /// Contains the output format, overriding the detection by the extension of the output path
+    pub format: Option<String>,
+    /// Contains the JPEG quality as u8 from 1 (smallest) to 100 (best)
+    pub quality: Option<u8>,
+    /// Contains the compression level of the PNG encoder
+    pub png_compression: Option<CompressionType>,
+    /// Contains the filter type of the PNG encoder
+    pub png_filter: Option<FilterType>,
+    /// Contains the maximum file size in bytes, which is met by lowering the JPEG quality
+    pub max_bytes: Option<u64>,
+}
+
+impl Encoding {
+    /// This function validates the supplied encoder settings
+    ///
+    /// Returns a new `Encoding`, or an `Error` if a setting is unknown or out of range
+    ///
+    /// # Arguments
+    ///
+    /// * `arg` - The argument name (or pipeline entry) the settings were supplied for, to report errors
+    /// * `format` - The name of the output format
+    /// * `quality` - The JPEG quality as u8 from 1 to 100
+    /// * `png_compression` - The name of the PNG compression level, one of `PNG_COMPRESSIONS`
+    /// * `png_filter` - The name of the PNG filter type, one of `PNG_FILTERS`
+    /// * `max_bytes` - The maximum file size in bytes
+    ///
+    /// # Examples
+    /// ```
+    /// # use image::codecs::png::CompressionType;
+    /// # use thumbnailer_cli::format::Encoding;
+    /// let encoding = Encoding::new("quality", None, Some(85), Some("best"), None, None).unwrap();
+    /// assert_eq!(encoding.png_compression, Some(CompressionType::Best));
+    /// ```
+    pub fn new(
+        arg: &str,
+        format: Option<String>,
+        quality: Option<u8>,
+        png_compression: Option<&str>,
+        png_filter: Option<&str>,
+        max_bytes: Option<u64>,
+    ) -> Result<Self> {
+        if let Some(format) = &format {
+            target_format(arg, format)?;
+        }
+        if let Some(quality) = quality.filter(|quality| !(1..=100).contains(quality)) {
+            return Err(Error::argument(
+                arg,
+                format!("quality expects a value from 1 to 100, got {}", quality),
+            ));
+        }
+
+        let png_compression = match png_compression.map(str::to_lowercase).as_deref() {
+            None => None,
+            Some("fast") => Some(CompressionType::Fast),
+            Some("default") => Some(CompressionType::Default),
+            Some("best") => Some(CompressionType::Best),
+            Some("huffman") => Some(CompressionType::Huffman),
+            Some("rle") => Some(CompressionType::Rle),
+            Some(name) => {
+                return Err(Error::argument(
+                    arg,
+                    format!(
+                        "png_compression expects one of {}, got {}",
+                        PNG_COMPRESSIONS.join(", "),
+                        name
+                    ),
+                ))
+            }
+        };
+        let png_filter = match png_filter.map(str::to_lowercase).as_deref() {
+            None => None,
+            Some("none") => Some(FilterType::NoFilter),
+            Some("sub") => Some(FilterType::Sub),
+            Some("up") => Some(FilterType::Up),
+            Some("avg") => Some(FilterType::Avg),
+            Some("paeth") => Some(FilterType::Paeth),
+            Some(name) => {
+                return Err(Error::argument(
+                    arg,
+                    format!(
+                        "png_filter expects one of {}, got {}",
+                        PNG_FILTERS.join(", "),
+                        name
+                    ),
+                ))
+            }
+        };
+
+        Ok(Encoding {
+            format,
+            quality,
+            png_compression,
+            png_filter,
+            max_bytes,
+        })
+    }
+
+    /// This function checks, whether no encoder setting and no output format are supplied
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Encoding` struct
+    pub fn is_empty(&self) -> bool {
+        self.format.is_none()
+            && self.quality.is_none()
+            && self.png_compression.is_none()
+            && self.png_filter.is_none()
+            && self.max_bytes.is_none()
+    }
+
+    /// This function combines two `Encoding` structs, where the settings of `self` take precedence
+    ///
+    /// Returns a new `Encoding` with the settings of `self`, completed by the settings of `other`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `Encoding` with the higher precedence
+    /// * `other` - The `Encoding` to take unset settings from
+    pub fn or(self, other: &Encoding) -> Self {
+        Encoding {
+            format: self.format.or_else(|| other.format.clone()),
+            quality: self.quality.or(other.quality),
+            png_compression: self.png_compression.or(other.png_compression),
+            png_filter: self.png_filter.or(other.png_filter),
+            max_bytes: self.max_bytes.or(other.max_bytes),
+        }
+    }
+
+    /// This function checks, whether any of the encoder settings applies to the supplied format
+    ///
+    /// Only then the image has to be encoded by `encode` instead of `thumbnailer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Encoding`
+    /// * `format` - The `TargetFormat` the image is stored in
+    pub fn applies_to(&self, format: &TargetFormat) -> bool {
+        match format {
+            TargetFormat::Jpeg => self.quality.is_some() || self.max_bytes.is_some(),
+            TargetFormat::Png => self.png_compression.is_some() || self.png_filter.is_some(),
+            _ => false,
+        }
+    }
+
+    /// This function encodes the supplied image with the encoder settings
+    ///
+    /// Returns the encoded image as bytes, or an `Error::Store` if it could not be encoded
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Encoding`
+    /// * `image` - The decoded image
+    /// * `format` - The `TargetFormat` to encode the image in, `TargetFormat::Jpeg` or `TargetFormat::Png`
+    /// * `output` - The output path of the image, to report errors
+    pub fn encode(
+        &self,
+        image: &DynamicImage,
+        format: &TargetFormat,
+        output: &Path,
+    ) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        // The PNG encoder expects big endian samples, so 16-bit images are converted to 8-bit first
+        let image8 = match image.color() {
+            ColorType::L16 => Cow::Owned(DynamicImage::ImageLuma8(image.to_luma8())),
+            ColorType::La16 => Cow::Owned(DynamicImage::ImageLumaA8(image.to_luma_alpha8())),
+            ColorType::Rgb16 => Cow::Owned(DynamicImage::ImageRgb8(image.to_rgb8())),
+            ColorType::Rgba16 => Cow::Owned(DynamicImage::ImageRgba8(image.to_rgba8())),
+            _ => Cow::Borrowed(image),
+        };
+        let result = match format {
+            TargetFormat::Png => PngEncoder::new_with_quality(
+                &mut bytes,
+                self.png_compression.unwrap_or(CompressionType::Fast),
+                self.png_filter.unwrap_or(FilterType::Sub),
+            )
+            .encode(
+                image8.as_bytes(),
+                image8.width(),
+                image8.height(),
+                image8.color(),
+            ),
+            _ => JpegEncoder::new_with_quality(&mut bytes, self.quality.unwrap_or(DEFAULT_QUALITY))
+                .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8())),
+        };
+
+        result.map(|_| bytes).map_err(|_| Error::Store {
+            arg: String::from(NAME_FILE_OUT),
+            path: output.display().to_string(),
+        })
+    }
+
+    /// This function encodes the supplied image as JPEG with the highest quality, which fits into `max_bytes`
+    ///
+    /// The quality is searched binary from 1 up to the supplied quality (100 by default).
+    ///
+    /// Returns the encoded image as bytes and the chosen quality, or an `Error::Budget` if even the lowest quality does not fit
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Encoding`
+    /// * `image` - The decoded image
+    /// * `max_bytes` - The maximum file size in bytes
+    /// * `output` - The output path of the image, to report errors
+    pub fn encode_max_bytes(
+        &self,
+        image: &DynamicImage,
+        max_bytes: u64,
+        output: &Path,
+    ) -> Result<(Vec<u8>, u8)> {
+        let image = DynamicImage::ImageRgb8(image.to_rgb8());
+        let encode = |quality: u8| -> Result<Vec<u8>> {
+            let mut bytes = vec![];
+            JpegEncoder::new_with_quality(&mut bytes, quality)
+                .encode_image(&image)
+                .map(|_| bytes)
+                .map_err(|_| Error::Store {
+                    arg: String::from(NAME_FILE_OUT),
+                    path: output.display().to_string(),
+                })
+        };
+
+        let mut best = None;
+        let (mut low, mut high) = (1, self.quality.unwrap_or(100));
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let bytes = encode(quality)?;
+            if bytes.len() as u64 <= max_bytes {
+                best = Some((bytes, quality));
+                low = quality + 1;
+            } else if quality == 1 {
+                return Err(Error::Budget {
+                    arg: String::from(ARG_MAX_BYTES),
+                    path: output.display().to_string(),
+                    max_bytes,
+                    size: bytes.len() as u64,
+                });
+            } else {
+                high = quality - 1;
+            }
+        }
+
+        // The loop ends either with a result or at quality 1, which returns above
+        Ok(best.unwrap())
+    }
+}
+
+/// This function returns the name of the supplied PNG compression level, one of `PNG_COMPRESSIONS`
+///
+/// # Arguments
+///
+/// * `png_compression` - The `CompressionType`
+pub fn png_compression_name(png_compression: CompressionType) -> &'static str {
+    match png_compression {
+        CompressionType::Fast => "fast",
+        CompressionType::Best => "best",
+        CompressionType::Huffman => "huffman",
+        CompressionType::Rle => "rle",
+        _ => "default",
+    }
+}
+
+/// This function returns the name of the supplied PNG filter type, one of `PNG_FILTERS`
+///
+/// # Arguments
+///
+/// * `png_filter` - The `FilterType`
+pub fn png_filter_name(png_filter: FilterType) -> &'static str {
+    match png_filter {
+        FilterType::NoFilter => "none",
+        FilterType::Sub => "sub",
+        FilterType::Up => "up",
+        FilterType::Avg => "avg",
+        _ => "paeth",
+    }
+}
+
+impl fmt::Display for Encoding {
+    /// This function formats the supplied encoder settings, such as `quality 85, png_filter paeth`
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Encoding`
+    /// * `f` - The `Formatter`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = vec![];
+        if let Some(format) = &self.format {
+            settings.push(format!("format {}", format));
+        }
+        if let Some(quality) = self.quality {
+            settings.push(format!("quality {}", quality));
+        }
+        if let Some(png_compression) = self.png_compression {
+            settings.push(format!(
+                "png_compression {}",
+                png_compression_name(png_compression)
+            ));
+        }
+        if let Some(png_filter) = self.png_filter {
+            settings.push(format!("png_filter {}", png_filter_name(png_filter)));
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            settings.push(format!("max_bytes {}", max_bytes));
+        }
+        write!(f, "{}", settings.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgb, RgbImage};
+
+    use super::*;
+
+    /// The argument name used to report errors
+    const ARG: &str = "format";
+
+    /// This function returns a noisy image, which does not compress well
+    fn noise(width: u32, height: u32) -> DynamicImage {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            let value = (x * 7919 + y * 104_729) ^ (x * y * 31);
+            Rgb([value as u8, (value >> 8) as u8, (value >> 16) as u8])
+        });
+        DynamicImage::ImageRgb8(image)
+    }
+
+    /// This function returns the name of the supplied format, to compare formats without `PartialEq`
+    fn name(format: Result<TargetFormat>) -> &'static str {
+        format.map_or("error", |format| format_name(&format))
+    }
+
+    #[test]
+    fn formats_are_detected_by_their_aliases_in_any_case() {
+        for alias in &["jpg", "JPG", "jpeg", "Jpe", "JFIF"] {
+            assert_eq!(name(target_format(ARG, alias)), "jpeg", "{}", alias);
+        }
+        for (alias, format) in &[
+            ("PNG", "png"),
+            ("tif", "tiff"),
+            ("Dib", "bmp"),
+            ("gif", "gif"),
+        ] {
+            assert_eq!(name(target_format(ARG, alias)), *format, "{}", alias);
+        }
+        match target_format(ARG, "pgn") {
+            Err(Error::UnsupportedFormat { arg, format }) => {
+                assert_eq!(arg, ARG);
+                assert_eq!(format, "pgn");
+            }
+            _ => panic!("expected an error for an unknown format"),
+        }
+    }
+
+    #[test]
+    fn output_formats_without_extension_require_a_format() {
+        assert_eq!(
+            name(output_format(Path::new("out/photo.JPEG"), None)),
+            "jpeg"
+        );
+        assert_eq!(
+            name(output_format(Path::new("out/photo"), Some("png"))),
+            "png"
+        );
+        assert_eq!(
+            name(output_format(Path::new("out/photo.jpg"), Some("png"))),
+            "png"
+        );
+        match output_format(Path::new("out/photo"), None) {
+            Err(Error::Argument { arg, message }) => {
+                assert_eq!(arg, NAME_FILE_OUT);
+                assert!(message.contains("has no extension"), "{}", message);
+            }
+            _ => panic!("expected an error for an output path without extension"),
+        }
+    }
+
+    #[test]
+    fn the_highest_quality_within_the_maximum_file_size_is_chosen() {
+        let image = noise(64, 64);
+        let encoding = Encoding::default();
+        let output = Path::new("out.jpg");
+        let size = |quality: u8| {
+            let encoding = Encoding {
+                quality: Some(quality),
+                ..Encoding::default()
+            };
+            encoding
+                .encode(&image, &TargetFormat::Jpeg, output)
+                .unwrap()
+                .len() as u64
+        };
+
+        let max_bytes = (size(40) + size(41)) / 2;
+        let (bytes, quality) = encoding
+            .encode_max_bytes(&image, max_bytes, output)
+            .unwrap();
+        assert!(bytes.len() as u64 <= max_bytes);
+        assert_eq!(bytes.len() as u64, size(quality));
+        assert!(size(quality + 1) > max_bytes, "quality {}", quality);
+
+        let limited = Encoding {
+            quality: Some(30),
+            ..Encoding::default()
+        };
+        let (_, quality) = limited.encode_max_bytes(&image, u64::MAX, output).unwrap();
+        assert_eq!(quality, 30);
+    }
+
+    #[test]
+    fn images_exceeding_the_maximum_file_size_at_the_lowest_quality_are_rejected() {
+        let image = noise(64, 64);
+        match Encoding::default().encode_max_bytes(&image, 100, Path::new("out.jpg")) {
+            Err(Error::Budget {
+                arg,
+                path,
+                max_bytes,
+                size,
+            }) => {
+                assert_eq!(arg, ARG_MAX_BYTES);
+                assert_eq!(path, "out.jpg");
+                assert_eq!(max_bytes, 100);
+                assert!(size > 100);
+            }
+            _ => panic!("expected an error for a file size budget, which can not be met"),
+        }
+    }
+
+    #[test]
+    fn png_images_with_16_bit_samples_are_stored_with_8_bit_samples() {
+        let image = DynamicImage::ImageRgb16(ImageBuffer::from_fn(4, 3, |x, y| {
+            Rgb([0x1234 * x as u16, 0xff00, 0x00ff * y as u16])
+        }));
+        let bytes = Encoding::default()
+            .encode(&image, &TargetFormat::Png, Path::new("out.png"))
+            .unwrap();
+        let stored = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(stored.color(), ColorType::Rgb8);
+        assert_eq!(stored.to_rgb8(), image.to_rgb8());
+    }
+}
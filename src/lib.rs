@@ -0,0 +1,29 @@
+//! Library of the thumbnailer command line interface (`thumbnailer_cli`)
+//!
+//! Contains the commands, which apply the operations provided by `thumbnailer` to images, the parser of the command line arguments, pipeline files, presets and `--ops`,
+//! the processing of single images, batches and the watch mode by `run` and `run_watch`,
+//! and the `PipelineBuilder` to apply the same commands from Rust, without running the command line interface.
+extern crate clap;
+extern crate image;
+
+pub use crate::builder::PipelineBuilder;
+pub use crate::cli::{read_commands, Commands, Rendition};
+pub use crate::commands::Command;
+pub use crate::error::{Error, Result};
+pub use crate::runner::{run, run_watch, Options};
+
+pub mod batch;
+pub mod builder;
+pub mod cli;
+pub mod commands;
+pub mod error;
+pub mod format;
+pub mod manifest;
+pub mod process;
+pub mod profile;
+pub mod report;
+pub mod runner;
+pub mod stdio;
+pub mod store;
+pub mod template;
+pub mod watch;
@@ -1,55 +1,114 @@
 extern crate clap;
-extern crate image;
 
-use std::ffi::OsStr;
-use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
 
-use thumbnailer::{GenericThumbnail, Target, Thumbnail};
-use thumbnailer::target::TargetFormat;
+use clap::ArgMatches;
 
-use crate::cli::{get_matches, NAME_FILE_IN, NAME_FILE_OUT, read_commands};
-
-pub mod cli;
-pub mod commands;
+use thumbnailer_cli::batch::collect_tasks;
+use thumbnailer_cli::cli::{
+    ARG_DRY_RUN, ARG_EMIT_PIPELINE, ARG_INCREMENTAL, ARG_INTERVAL, ARG_JOBS, ARG_LIST_PRESETS,
+    ARG_PROFILE, ARG_RECURSIVE, ARG_REPORT, ARG_SAVE_STEPS, ARG_WATCH, get_matches, NAME_PATHS,
+    read_commands,
+};
+use thumbnailer_cli::cli::parser::parse_value;
+use thumbnailer_cli::cli::pipeline::emit_pipeline;
+use thumbnailer_cli::cli::preset::list_presets;
+use thumbnailer_cli::error::{Error, Result};
+use thumbnailer_cli::report::Report;
+use thumbnailer_cli::runner::{run, run_watch, Options};
 
 /// Main logic of the thumbnailer command line interface (`thumbnailer_cli`)
 ///
 /// Run `thumbnailer_cli.exe -h` to view the help and learn about its functionality.
 /// Run `thumbnailer_cli.exe -V` to print the version information.
+///
+/// Errors are printed to stderr and end the process with the exit code of the `Error`.
 fn main() {
-    let matches = get_matches();
-
-    let file_in = String::from(matches.value_of(NAME_FILE_IN).unwrap());
-    let file_out = String::from(matches.value_of(NAME_FILE_OUT).unwrap());
-    let cmd_list = read_commands(matches);
-
-    println!("Input file: {}", file_in);
-    let mut image = Thumbnail::load(Path::new(&file_in).to_path_buf()).unwrap_or_else(|_| {
-        panic!(
-            "‼→ ERROR in {}: failed to load the image with the supplied path ←‼",
-            NAME_FILE_IN
-        )
-    });
-
-    for i in 0..cmd_list.commands.len() {
-        println!("{}", cmd_list.commands.get(i).unwrap().print());
-        cmd_list.commands.get(i).unwrap().execute(&mut image);
+    if let Err(error) = run_arguments(get_matches()) {
+        print_error("", &error);
+        process::exit(error.exit_code());
+    }
+}
+
+/// This function prints the supplied error to stderr, decorated to stand out of the progress messages
+///
+/// Only the first line of the message is decorated, further lines such as the caret below an `--ops` value follow undecorated.
+///
+/// # Arguments
+///
+/// * `prefix` - The prefix of the message, such as the input path of an image of a batch
+/// * `error` - The `Error` which occurred
+fn print_error(prefix: &str, error: &Error) {
+    let message = error.to_string();
+    let mut lines = message.lines();
+    eprintln!("{}‼→ ERROR in {} ←‼", prefix, lines.next().unwrap_or(""));
+    for line in lines {
+        eprintln!("{}{}", prefix, line);
+    }
+}
+
+/// This function reads the supplied arguments and runs the library on the supplied paths
+///
+/// The commands are parsed once and shared by all images. Errors of single images of a batch are printed and the remaining images are processed anyway.
+///
+/// Returns `()`, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `matches` - The `ArgMatches` struct from clap, containing the provided arguments
+fn run_arguments(matches: ArgMatches<'static>) -> Result<()> {
+    if matches.is_present(ARG_LIST_PRESETS) {
+        return list_presets();
     }
 
-    println!("Output file: {}", file_out);
-    let path = Path::new(&file_out);
-    let format = match path.extension().and_then(OsStr::to_str) {
-        Some("png") => TargetFormat::Png,
-        Some("tiff") => TargetFormat::Tiff,
-        Some("bmp") => TargetFormat::Bmp,
-        Some("gif") => TargetFormat::Gif,
-        _ => TargetFormat::Jpeg,
+    let paths: Vec<String> = matches
+        .values_of(NAME_PATHS)
+        .map_or(vec![], |paths| paths.map(String::from).collect());
+    let recursive = matches.is_present(ARG_RECURSIVE);
+    let jobs = match parse_value::<usize>(ARG_JOBS, "N", matches.value_of(ARG_JOBS).unwrap())? {
+        0 => thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        jobs => jobs,
+    };
+    let manifest = matches.value_of(ARG_INCREMENTAL).map(PathBuf::from);
+    let interval = match matches.is_present(ARG_WATCH) {
+        true => {
+            let interval = matches.value_of(ARG_INTERVAL).unwrap();
+            Some(parse_value::<u64>(ARG_INTERVAL, "seconds", interval)?)
+        }
+        false => None,
+    };
+    let (json, profile) = (matches.is_present(ARG_REPORT), matches.is_present(ARG_PROFILE));
+    let report = match json || profile {
+        true => Some(Report::new(json, profile)),
+        false => None,
     };
-    let target = Target::new(format, Path::new(&file_out).to_path_buf());
-    image.apply_store(&target).unwrap_or_else(|_| {
-        panic!(
-            "‼→ ERROR in {}: failed to store the image to the supplied path ←‼",
-            NAME_FILE_OUT
-        )
-    });
+    let dry_run = matches.is_present(ARG_DRY_RUN);
+    let save_steps = matches.value_of(ARG_SAVE_STEPS).map(PathBuf::from);
+    let emit = matches.value_of(ARG_EMIT_PIPELINE).map(String::from);
+    let cmd_list = read_commands(matches)?;
+
+    // Without paths, the pipeline file is only written
+    if let Some(emit) = emit {
+        emit_pipeline(&cmd_list, &emit)?;
+        if paths.is_empty() {
+            return Ok(());
+        }
+    }
+
+    let options = Options {
+        manifest,
+        report,
+        dry_run,
+        save_steps,
+        jobs,
+        on_error: print_error,
+    };
+    if let Some(interval) = interval {
+        return run_watch(&cmd_list, &paths, recursive, Duration::from_secs(interval), &options);
+    }
+    let tasks = collect_tasks(&paths, recursive)?;
+    run(&cmd_list, &tasks, options)
 }
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::Task;
+use crate::cli::{Commands, ARG_INCREMENTAL};
+use crate::commands::{Command, Description};
+use crate::error::{Error, Result};
+use crate::template::content_hash;
+
+/// Representation of the manifest file of incremental builds as a struct
+#[derive(Default, Deserialize, Serialize)]
+struct Manifest {
+    /// Contains the entry of each processed image, by its input path
+    #[serde(default)]
+    images: BTreeMap<String, Entry>,
+}
+
+/// Representation of a processed image in the manifest file as a struct
+#[derive(Deserialize, PartialEq, Serialize)]
+struct Entry {
+    /// Contains the hex-encoded SHA-256 hash of the input image
+    input_hash: String,
+    /// Contains the hex-encoded SHA-256 hash of the commands, the renditions and the encoder settings
+    commands_hash: String,
+    /// Contains the output path or output template of the image, as supplied
+    #[serde(default)]
+    output: String,
+    /// Contains the (resolved) paths of all outputs of the image
+    outputs: Vec<String>,
+}
+
+/// Representation of an incremental build as a struct
+///
+/// Images are skipped, if neither the input image, the output path nor the commands (including presets and the photos of combine-commands) changed since they were processed, and all their outputs still exist.
+pub struct Incremental {
+    /// Contains the path of the manifest file
+    path: PathBuf,
+    /// Contains the hash of the commands of this run
+    commands_hash: String,
+    /// Contains the manifest, which is shared by all worker threads
+    manifest: Mutex<Manifest>,
+}
+
+impl Incremental {
+    /// This function reads the supplied manifest file, which is created if it does not exist yet
+    ///
+    /// Returns a new `Incremental` struct, or an `Error` if the manifest or the photo of a combine-command could not be read
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the manifest file
+    /// * `cmd_list` - The `Commands` struct, containing the commands of this run
+    pub fn open(path: &Path, cmd_list: &Commands) -> Result<Self> {
+        let manifest = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                Error::argument(ARG_INCREMENTAL, format!("{}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Manifest::default(),
+            Err(source) => return Err(io_error(path, source)),
+        };
+
+        Ok(Incremental {
+            path: path.to_path_buf(),
+            commands_hash: commands_hash(cmd_list)?,
+            manifest: Mutex::new(manifest),
+        })
+    }
+
+    /// This function checks, whether the supplied image has to be processed
+    ///
+    /// Returns the hash of the input image, if it has to be processed, or `None` if its outputs are up to date
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Incremental` struct
+    /// * `task` - The `Task` struct, containing the input and output path of the image
+    pub fn check(&self, task: &Task) -> Result<Option<String>> {
+        let input = &task.input;
+        let input_hash = content_hash(&fs::read(input).map_err(|source| io_error(input, source))?);
+
+        let manifest = self.manifest.lock().unwrap();
+        let up_to_date = manifest
+            .images
+            .get(&input.display().to_string())
+            .filter(|entry| {
+                entry.input_hash == input_hash
+                    && entry.commands_hash == self.commands_hash
+                    && Path::new(&entry.output) == task.output
+            })
+            .is_some_and(|entry| {
+                entry
+                    .outputs
+                    .iter()
+                    .all(|output| Path::new(output).exists())
+            });
+
+        Ok(if up_to_date { None } else { Some(input_hash) })
+    }
+
+    /// This function records the supplied processed image in the manifest
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Incremental` struct
+    /// * `task` - The `Task` struct, containing the input and output path of the image
+    /// * `input_hash` - The hash of the input image, as returned by `check`
+    /// * `outputs` - The (resolved) paths of all outputs of the image
+    pub fn record(&self, task: &Task, input_hash: String, outputs: Vec<String>) {
+        let entry = Entry {
+            input_hash,
+            commands_hash: self.commands_hash.clone(),
+            output: task.output.display().to_string(),
+            outputs,
+        };
+
+        let mut manifest = self.manifest.lock().unwrap();
+        manifest
+            .images
+            .insert(task.input.display().to_string(), entry);
+    }
+
+    /// This function writes the manifest file
+    ///
+    /// Returns `()`, or an `Error::Io` if the manifest could not be written
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Incremental` struct
+    pub fn save(&self) -> Result<()> {
+        let manifest = self.manifest.lock().unwrap();
+        let content = serde_json::to_string_pretty(&*manifest).unwrap();
+        fs::write(&self.path, content).map_err(|source| io_error(&self.path, source))
+    }
+}
+
+/// This function returns the hash of everything, which affects the outputs besides the input image
+///
+/// The hash covers the version of `thumbnailer_cli`, each command as described by `Command::describe` without its `index`, the content of all files the commands depend on, the renditions and the encoder settings.
+/// The `index` only orders the commands, so adding or moving unrelated arguments does not change the hash.
+///
+/// Returns the hex-encoded SHA-256 hash, or an `Error::Io` if a dependency could not be read
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct, containing the commands of this run
+fn commands_hash(cmd_list: &Commands) -> Result<String> {
+    let mut description = vec![String::from(env!("CARGO_PKG_VERSION"))];
+    describe_commands(&cmd_list.commands, &mut description)?;
+    description.push(format!("{:?}", cmd_list.encoding));
+
+    for rendition in &cmd_list.renditions {
+        description.push(format!("{} -> {}", rendition.name, rendition.output));
+        describe_commands(&rendition.commands, &mut description)?;
+        description.push(format!("{:?}", rendition.encoding));
+    }
+
+    Ok(content_hash(description.join("\n").as_bytes()))
+}
+
+/// This function adds the description of the supplied commands and the hash of their dependencies to the list
+///
+/// # Arguments
+///
+/// * `commands` - The implementors of `Command` to describe
+/// * `description` - The list to add the lines of the description to
+fn describe_commands(commands: &[Box<dyn Command>], description: &mut Vec<String>) -> Result<()> {
+    for command in commands {
+        let Description {
+            command: name,
+            parameters,
+            ..
+        } = command.describe();
+        description.push(format!("{} {}", name, parameters));
+        for path in command.dependencies() {
+            let content = fs::read(path).map_err(|source| io_error(path, source))?;
+            description.push(content_hash(&content));
+        }
+    }
+    Ok(())
+}
+
+/// This function returns an `Error::Io` of incremental builds for the supplied path
+///
+/// # Arguments
+///
+/// * `path` - The path of the file, which could not be read or written
+/// * `source` - The underlying `io::Error`
+fn io_error(path: &Path, source: io::Error) -> Error {
+    Error::Io {
+        arg: String::from(ARG_INCREMENTAL),
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::commands::{CmdBlur, CmdInvert};
+
+    /// This function returns a `Commands` struct with the supplied commands
+    fn cmd_list(commands: Vec<Box<dyn Command>>) -> Commands {
+        Commands {
+            commands,
+            ..Commands::default()
+        }
+    }
+
+    #[test]
+    fn commands_hash_ignores_the_index_of_the_commands() {
+        let first = cmd_list(vec![
+            Box::new(CmdBlur::new(1, 2.5)),
+            Box::new(CmdInvert::new(2)),
+        ]);
+        let moved = cmd_list(vec![
+            Box::new(CmdBlur::new(4, 2.5)),
+            Box::new(CmdInvert::new(9)),
+        ]);
+        let changed = cmd_list(vec![
+            Box::new(CmdBlur::new(1, 3.0)),
+            Box::new(CmdInvert::new(2)),
+        ]);
+
+        assert_eq!(
+            commands_hash(&first).unwrap(),
+            commands_hash(&moved).unwrap()
+        );
+        assert_ne!(
+            commands_hash(&first).unwrap(),
+            commands_hash(&changed).unwrap()
+        );
+    }
+
+    #[test]
+    fn images_with_another_output_path_are_not_up_to_date() {
+        let dir = env::temp_dir().join(format!("thumbnailer_manifest_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("out.png");
+        fs::write(&output, b"stored").unwrap();
+
+        let incremental =
+            Incremental::open(&dir.join("manifest.json"), &Commands::default()).unwrap();
+        let task = |output: &Path| Task {
+            input: PathBuf::from("resources/tests/test_small.png"),
+            output: output.to_path_buf(),
+            index: 0,
+        };
+        let input_hash = incremental.check(&task(&output)).unwrap().unwrap();
+        incremental.record(
+            &task(&output),
+            input_hash,
+            vec![output.display().to_string()],
+        );
+
+        assert!(incremental.check(&task(&output)).unwrap().is_none());
+        assert!(incremental
+            .check(&task(&dir.join("other.png")))
+            .unwrap()
+            .is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
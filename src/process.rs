@@ -0,0 +1,489 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use thumbnailer::target::TargetFormat;
+use thumbnailer::{GenericThumbnail, Target, Thumbnail};
+
+use crate::batch::Task;
+use crate::cli::{Commands, ARG_DRY_RUN, ARG_SAVE_STEPS, NAME_FILE_IN};
+use crate::commands::exif::keeps_metadata;
+use crate::commands::Command;
+use crate::error::{Error, Result};
+use crate::format::{format_name, output_format, Encoding};
+use crate::report::{millis_since, ImageReport, OutputReport, Status, StepReport};
+use crate::stdio::{is_stdio, read_stdin, TempFile};
+use crate::store::{check_metadata, create_parent_dir, store_image};
+use crate::template::{is_template, OutputTemplate};
+
+/// This function loads a single image, applies all commands and stores the result
+///
+/// Returns the `ImageReport` of the image, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input and output path of the image
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `steps_dir` - The directory to save the image to after each command, if any
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+pub(crate) fn process_image(
+    task: &Task,
+    cmd_list: &Commands,
+    steps_dir: Option<&Path>,
+    progress: &Progress,
+) -> Result<ImageReport> {
+    let start = Instant::now();
+    let mut report = ImageReport::new(task, Status::Processed);
+
+    // The image of stdin is kept in a temporary file until the image is processed
+    let (_stdin, file_in, path_in) = read_input(task)?;
+
+    progress.print(format!("Input file: {}", file_in));
+    read_header(&path_in, &mut report);
+
+    let load = Instant::now();
+    let mut image = Thumbnail::load(path_in).map_err(|_| Error::Load {
+        arg: String::from(NAME_FILE_IN),
+        path: file_in.clone(),
+    })?;
+    report.load_millis = Some(millis_since(load));
+
+    if let Some(steps_dir) = steps_dir {
+        save_step(&mut image, steps_dir, 0, "input", progress)?;
+    }
+    for (i, command) in cmd_list.commands.iter().enumerate() {
+        let step = execute_command(command.as_ref(), &mut image, None, progress);
+        if let Some(steps_dir) = steps_dir {
+            save_step(
+                &mut image,
+                steps_dir,
+                i + 1,
+                step.description.command,
+                progress,
+            )?;
+        }
+        report.steps.push(step);
+    }
+
+    if cmd_list.renditions.is_empty() {
+        let keeps_metadata = keeps_metadata(cmd_list.commands.iter());
+        let output = store_image(
+            image,
+            task,
+            &task.output,
+            &cmd_list.encoding,
+            keeps_metadata,
+            progress,
+        )?;
+        report.outputs.push(output);
+        report.millis = millis_since(start);
+        return Ok(report);
+    }
+
+    // The image is loaded once and only copied where the commands of the renditions diverge
+    let output_dir = task.output.parent().unwrap_or_else(|| Path::new(""));
+    for rendition in &cmd_list.renditions {
+        let progress = progress.nested(&rendition.name);
+        let mut copy = image.clone_static_copy().ok_or_else(|| Error::Load {
+            arg: String::from(NAME_FILE_IN),
+            path: file_in.clone(),
+        })?;
+
+        for command in &rendition.commands {
+            let name = Some(rendition.name.as_str());
+            let step = execute_command(command.as_ref(), &mut copy, name, &progress);
+            report.steps.push(step);
+        }
+        let output = output_dir.join(&rendition.output);
+        let encoding = rendition.encoding.clone().or(&cmd_list.encoding);
+        let keeps_metadata = keeps_metadata(cmd_list.commands.iter().chain(&rendition.commands));
+        let mut output = store_image(copy, task, &output, &encoding, keeps_metadata, &progress)?;
+        output.rendition = Some(rendition.name.clone());
+        report.outputs.push(output);
+    }
+
+    report.millis = millis_since(start);
+    Ok(report)
+}
+
+/// This function saves a copy of the image as PNG to the supplied directory, named `NN_<command>.png`
+///
+/// Returns `()`, or an `Error` if the image could not be copied or stored
+///
+/// # Arguments
+///
+/// * `image` - The image after executing the command
+/// * `steps_dir` - The directory to save the image to
+/// * `step` - The position of the command in the order of execution, starting at 1 (0 for the loaded image)
+/// * `command` - The name of the command
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+fn save_step(
+    image: &mut Thumbnail,
+    steps_dir: &Path,
+    step: usize,
+    command: &str,
+    progress: &Progress,
+) -> Result<()> {
+    let path = steps_dir.join(format!("{:02}_{}.png", step, command));
+    let store_error = || Error::Store {
+        arg: String::from(ARG_SAVE_STEPS),
+        path: path.display().to_string(),
+    };
+
+    let copy = image.clone_static_copy().ok_or_else(store_error)?;
+    create_parent_dir(&path)?;
+    copy.apply_store(&Target::new(TargetFormat::Png, path.clone()))
+        .map_err(|_| store_error())?;
+    progress.print(format!("Step file: {}", path.display()));
+    Ok(())
+}
+
+/// This function plans a single image by simulating all commands on its dimensions, without decoding or storing it
+///
+/// Only the header of the image is read. All problems, such as a crop box outside of the image, are collected.
+///
+/// Returns the `ImageReport` of the planned image, or an `Error::Bounds` containing all problems
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input and output path of the image
+/// * `cmd_list` - The `Commands` struct, containing the commands to simulate
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+pub(crate) fn plan_image(
+    task: &Task,
+    cmd_list: &Commands,
+    progress: &Progress,
+) -> Result<ImageReport> {
+    let start = Instant::now();
+    let mut report = ImageReport::new(task, Status::Planned);
+
+    let (_stdin, file_in, path_in) = read_input(task)?;
+    read_header(&path_in, &mut report);
+    let dimensions = match (report.width, report.height) {
+        (Some(width), Some(height)) => (width, height),
+        _ => {
+            return Err(Error::Load {
+                arg: String::from(NAME_FILE_IN),
+                path: file_in,
+            })
+        }
+    };
+    progress.print(format!(
+        "Input file: {} ({}x{})",
+        file_in, dimensions.0, dimensions.1
+    ));
+
+    let plan = plan(cmd_list, dimensions, progress);
+    report.steps = plan.steps;
+
+    if cmd_list.renditions.is_empty() {
+        let keeps_metadata = keeps_metadata(cmd_list.commands.iter());
+        let output = plan_output(
+            task,
+            &task.output,
+            &cmd_list.encoding,
+            keeps_metadata,
+            plan.outputs[0],
+            progress,
+        )?;
+        report.outputs.push(output);
+    }
+    let output_dir = task.output.parent().unwrap_or_else(|| Path::new(""));
+    for (rendition, dimensions) in cmd_list.renditions.iter().zip(plan.outputs) {
+        let progress = progress.nested(&rendition.name);
+        let output = output_dir.join(&rendition.output);
+        let encoding = rendition.encoding.clone().or(&cmd_list.encoding);
+        let keeps_metadata = keeps_metadata(cmd_list.commands.iter().chain(&rendition.commands));
+        let mut output = plan_output(
+            task,
+            &output,
+            &encoding,
+            keeps_metadata,
+            dimensions,
+            &progress,
+        )?;
+        output.rendition = Some(rendition.name.clone());
+        report.outputs.push(output);
+    }
+
+    if !plan.problems.is_empty() {
+        return Err(Error::Bounds {
+            arg: String::from(ARG_DRY_RUN),
+            path: file_in,
+            problems: plan.problems,
+        });
+    }
+    report.millis = millis_since(start);
+    Ok(report)
+}
+
+/// Representation of the simulated commands of an image as a struct
+pub(crate) struct Plan {
+    /// Contains the `StepReport` of each command, the commands of each rendition following the common ones
+    pub(crate) steps: Vec<StepReport>,
+    /// Contains the planned dimensions of each output, a single one without renditions or one per rendition
+    pub(crate) outputs: Vec<(u32, u32)>,
+    /// Contains the problems of the commands, which do not fit the image
+    pub(crate) problems: Vec<String>,
+}
+
+/// This function simulates the commands of the image and of each rendition on the supplied dimensions, without executing them
+///
+/// All problems, such as a crop box outside of the image, are collected.
+///
+/// Returns the `Plan` struct of the commands
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct, containing the commands to simulate
+/// * `dimensions` - The width and height of the image
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+pub(crate) fn plan(cmd_list: &Commands, dimensions: (u32, u32), progress: &Progress) -> Plan {
+    let mut plan = Plan {
+        steps: vec![],
+        outputs: vec![],
+        problems: vec![],
+    };
+
+    let mut base = dimensions;
+    for command in &cmd_list.commands {
+        let command = command.as_ref();
+        let step = plan_command(command, &mut base, None, &mut plan.problems, progress);
+        plan.steps.push(step);
+    }
+    if cmd_list.renditions.is_empty() {
+        plan.outputs.push(base);
+    }
+
+    for rendition in &cmd_list.renditions {
+        let progress = progress.nested(&rendition.name);
+        let mut dimensions = base;
+        for command in &rendition.commands {
+            let name = Some(rendition.name.as_str());
+            let command = command.as_ref();
+            let step = plan_command(
+                command,
+                &mut dimensions,
+                name,
+                &mut plan.problems,
+                &progress,
+            );
+            plan.steps.push(step);
+        }
+        plan.outputs.push(dimensions);
+    }
+    plan
+}
+
+/// This function simulates a single command on the dimensions of the image
+///
+/// Returns the `StepReport` of the command
+///
+/// # Arguments
+///
+/// * `command` - The implementor of `Command` to simulate
+/// * `dimensions` - The width and height of the image, which are updated to the dimensions after the command
+/// * `rendition` - The name of the rendition, if the command is only applied to it
+/// * `problems` - The list to add the problem of the command to, if it does not fit the image
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+fn plan_command(
+    command: &dyn Command,
+    dimensions: &mut (u32, u32),
+    rendition: Option<&str>,
+    problems: &mut Vec<String>,
+    progress: &Progress,
+) -> StepReport {
+    if let Err(problem) = command.validate(dimensions.0, dimensions.1) {
+        let problem = match rendition {
+            Some(rendition) => format!("{:02}. [{}] {}", command.get_index(), rendition, problem),
+            None => format!("{:02}. {}", command.get_index(), problem),
+        };
+        progress.print(format!("Problem: {}", problem));
+        problems.push(problem);
+    }
+    *dimensions = command.dimensions(dimensions.0, dimensions.1);
+    progress.print(format!(
+        "{}\t→ {}x{}",
+        command.print(),
+        dimensions.0,
+        dimensions.1
+    ));
+
+    StepReport {
+        rendition: rendition.map(String::from),
+        description: command.describe(),
+        width: dimensions.0,
+        height: dimensions.1,
+        millis: 0.0,
+    }
+}
+
+/// This function plans the output of an image, without storing it
+///
+/// Placeholders of the stored image, such as `{hash}`, are kept as they are in the planned output path.
+///
+/// Returns the `OutputReport` of the planned output, or an `Error` if the format is not supported or the metadata would be lost
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input path and the position of the image
+/// * `output` - The output path or output template to store the image to
+/// * `encoding` - The output format and the encoder settings
+/// * `keeps_metadata` - Whether the metadata of the source file is kept by an exif-command
+/// * `dimensions` - The planned width and height of the stored image
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+fn plan_output(
+    task: &Task,
+    output: &Path,
+    encoding: &Encoding,
+    keeps_metadata: bool,
+    dimensions: (u32, u32),
+    progress: &Progress,
+) -> Result<OutputReport> {
+    let output = match output.to_str().filter(|output| is_template(output)) {
+        Some(template) => OutputTemplate::parse(template)?.resolve(&task.input, task.index, None),
+        None => output.to_path_buf(),
+    };
+    let format = output_format(&output, encoding.format.as_deref())?;
+    check_metadata(keeps_metadata, encoding, &format, &output)?;
+    progress.print(format!(
+        "Output file: {} ({}, {}x{})",
+        output.display(),
+        format_name(&format),
+        dimensions.0,
+        dimensions.1
+    ));
+
+    Ok(OutputReport {
+        rendition: None,
+        path: output.display().to_string(),
+        format: String::from(format_name(&format)),
+        bytes: None,
+        width: Some(dimensions.0),
+        height: Some(dimensions.1),
+        quality: None,
+        store_millis: 0.0,
+        encode_millis: None,
+        millis: 0.0,
+    })
+}
+
+/// This function returns the path to load the image of the supplied task from
+///
+/// The image of stdin is read into a temporary file, which has to be kept until the image is loaded.
+///
+/// Returns the `TempFile` of stdin (if any), the name of the input to print and the path to load, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input path of the image
+fn read_input(task: &Task) -> Result<(Option<TempFile>, String, PathBuf)> {
+    if is_stdio(&task.input) {
+        let stdin = read_stdin()?;
+        let path = stdin.path.clone();
+        return Ok((Some(stdin), String::from("stdin"), path));
+    }
+    Ok((None, task.input.display().to_string(), task.input.clone()))
+}
+
+/// This function reads the format and the dimensions of the image from its header into the report
+///
+/// The pixels are not decoded. Images with an unknown header keep the format and the dimensions unset.
+///
+/// # Arguments
+///
+/// * `path` - The path of the image
+/// * `report` - The `ImageReport` of the image
+fn read_header(path: &Path, report: &mut ImageReport) {
+    let reader = image::io::Reader::open(path).and_then(|reader| reader.with_guessed_format());
+    if let Ok(reader) = reader {
+        report.format = reader
+            .format()
+            .map(|format| format!("{:?}", format).to_lowercase());
+        if let Ok((width, height)) = reader.into_dimensions() {
+            report.width = Some(width);
+            report.height = Some(height);
+        }
+    }
+}
+
+/// This function executes a single command on the image and measures it
+///
+/// Returns the `StepReport` of the command
+///
+/// # Arguments
+///
+/// * `command` - The implementor of `Command` to execute
+/// * `image` - The image to modify, whose dimensions are measured after the command
+/// * `rendition` - The name of the rendition, if the command is only applied to it
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+fn execute_command(
+    command: &dyn Command,
+    image: &mut dyn GenericThumbnail,
+    rendition: Option<&str>,
+    progress: &Progress,
+) -> StepReport {
+    progress.print(command.print());
+    let start = Instant::now();
+    command.execute(image);
+    let dimensions = image.get_size();
+
+    StepReport {
+        rendition: rendition.map(String::from),
+        description: command.describe(),
+        width: dimensions.0,
+        height: dimensions.1,
+        millis: millis_since(start),
+    }
+}
+
+/// Representation of the progress messages of a single image as a struct
+pub(crate) struct Progress {
+    /// Contains the prefix of all printed lines, to tell apart the images of a batch
+    pub(crate) prefix: String,
+    /// Contains whether the messages are printed to stderr, as stdout carries the image
+    pub(crate) stderr: bool,
+    /// Contains whether the messages are suppressed, as the watch mode and the report print their own output
+    pub(crate) quiet: bool,
+}
+
+impl Progress {
+    /// Returns a new `Progress` struct, which suppresses all messages
+    pub(crate) fn quiet() -> Self {
+        Progress {
+            prefix: String::new(),
+            stderr: false,
+            quiet: true,
+        }
+    }
+
+    /// This function prints the supplied message with the prefix
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Progress` struct
+    /// * `message` - The message to print
+    pub(crate) fn print(&self, message: String) {
+        if self.quiet {
+            return;
+        }
+        if self.stderr {
+            eprintln!("{}{}", self.prefix, message);
+        } else {
+            println!("{}{}", self.prefix, message);
+        }
+    }
+
+    /// This function returns a new `Progress` struct for a rendition of the image
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Progress` struct of the image
+    /// * `name` - The name of the rendition, which is appended to the prefix
+    pub(crate) fn nested(&self, name: &str) -> Progress {
+        Progress {
+            prefix: format!("{}[{}] ", self.prefix, name),
+            stderr: self.stderr,
+            quiet: self.quiet,
+        }
+    }
+}
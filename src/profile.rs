@@ -0,0 +1,205 @@
+use std::fs;
+
+use serde::Serialize;
+
+use crate::report::ImageReport;
+
+/// Representation of the wall times of a step type, such as `load` or `blur`, over all images as a struct
+#[derive(Serialize)]
+pub struct ProfileRow {
+    /// Contains the name of the step type
+    pub step: String,
+    /// Contains the number of measured steps
+    pub count: usize,
+    /// Contains the total wall time in milliseconds
+    pub total_millis: f64,
+    /// Contains the mean wall time in milliseconds
+    pub mean_millis: f64,
+    /// Contains the median wall time in milliseconds
+    pub p50_millis: f64,
+    /// Contains the 90th percentile of the wall time in milliseconds
+    pub p90_millis: f64,
+    /// Contains the 99th percentile of the wall time in milliseconds
+    pub p99_millis: f64,
+    /// Contains the maximum wall time in milliseconds
+    pub max_millis: f64,
+}
+
+/// Representation of the profile of a run as a struct
+#[derive(Serialize)]
+pub struct Profile {
+    /// Contains the aggregated wall times per step type, in the order they first occurred
+    pub steps: Vec<ProfileRow>,
+    /// Contains the wall time of the whole run in milliseconds
+    pub total_millis: f64,
+    /// Contains the peak resident memory of the process in bytes, if the platform reports it
+    pub peak_rss_bytes: Option<u64>,
+}
+
+impl Profile {
+    /// This function aggregates the wall times of all steps of the supplied images per step type
+    ///
+    /// The step types are `load` (`Thumbnail::load`), the name of each command (`Command::execute`),
+    /// `apply_store` and `encode` (encoding again with the encoder settings).
+    ///
+    /// Returns a new `Profile`
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - The `ImageReport` of all images of the run
+    /// * `total_millis` - The wall time of the whole run in milliseconds
+    pub fn new(images: &[ImageReport], total_millis: f64) -> Self {
+        let mut samples: Vec<(String, Vec<f64>)> = vec![];
+        let mut add =
+            |step: &str, millis: f64| match samples.iter_mut().find(|(name, _)| name == step) {
+                Some((_, values)) => values.push(millis),
+                None => samples.push((String::from(step), vec![millis])),
+            };
+
+        for image in images {
+            if let Some(millis) = image.load_millis {
+                add("load", millis);
+            }
+            for step in &image.steps {
+                add(step.description.command, step.millis);
+            }
+            for output in &image.outputs {
+                add("apply_store", output.store_millis);
+                if let Some(millis) = output.encode_millis {
+                    add("encode", millis);
+                }
+            }
+        }
+
+        Profile {
+            steps: samples
+                .into_iter()
+                .map(|(step, values)| ProfileRow::new(step, values))
+                .collect(),
+            total_millis,
+            peak_rss_bytes: peak_rss_bytes(),
+        }
+    }
+
+    /// This function formats the profile as table, which can be printed to the user
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Profile` struct
+    pub fn table(&self) -> String {
+        let mut table = format!(
+            "{:<16}{:>8}{:>12}{:>10}{:>10}{:>10}{:>10}{:>10}\n",
+            "STEP", "COUNT", "TOTAL ms", "MEAN ms", "P50 ms", "P90 ms", "P99 ms", "MAX ms"
+        );
+        for row in &self.steps {
+            table.push_str(&format!(
+                "{:<16}{:>8}{:>12.3}{:>10.3}{:>10.3}{:>10.3}{:>10.3}{:>10.3}\n",
+                row.step,
+                row.count,
+                row.total_millis,
+                row.mean_millis,
+                row.p50_millis,
+                row.p90_millis,
+                row.p99_millis,
+                row.max_millis
+            ));
+        }
+
+        table.push_str(&format!("Total wall time: {:.3} ms\n", self.total_millis));
+        match self.peak_rss_bytes {
+            Some(bytes) => table.push_str(&format!(
+                "Peak resident memory: {:.1} MiB",
+                bytes as f64 / (1024.0 * 1024.0)
+            )),
+            None => table.push_str("Peak resident memory: unknown"),
+        }
+        table
+    }
+}
+
+impl ProfileRow {
+    /// This function aggregates the supplied wall times of a step type
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - The name of the step type
+    /// * `values` - The wall times of all measured steps in milliseconds, which must not be empty
+    fn new(step: String, mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        let total: f64 = values.iter().sum();
+        let round = |millis: f64| (millis * 1000.0).round() / 1000.0;
+
+        ProfileRow {
+            step,
+            count: values.len(),
+            total_millis: round(total),
+            mean_millis: round(total / values.len() as f64),
+            p50_millis: percentile(&values, 50.0),
+            p90_millis: percentile(&values, 90.0),
+            p99_millis: percentile(&values, 99.0),
+            max_millis: values[values.len() - 1],
+        }
+    }
+}
+
+/// This function returns the percentile of the supplied sorted values by the nearest-rank method
+///
+/// # Arguments
+///
+/// * `sorted` - The values in ascending order, which must not be empty
+/// * `percent` - The percentile from 0 to 100
+///
+/// # Examples
+/// The example is not compiled, as `percentile` is private.
+///
+/// ```ignore
+/// assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 50.0), 2.0);
+/// assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 90.0), 4.0);
+/// ```
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// This function returns the peak resident memory of the process in bytes
+///
+/// Returns the peak as u64, or `None` if the platform does not report it (only Linux does via `/proc`)
+fn peak_rss_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_of_a_single_value_are_the_value() {
+        for percent in &[0.0, 50.0, 90.0, 99.0, 100.0] {
+            assert_eq!(percentile(&[7.5], *percent), 7.5);
+        }
+    }
+
+    #[test]
+    fn percentiles_of_two_values_split_at_the_median() {
+        let sorted = [1.0, 3.0];
+        assert_eq!(percentile(&sorted, 0.0), 1.0);
+        assert_eq!(percentile(&sorted, 50.0), 1.0);
+        assert_eq!(percentile(&sorted, 51.0), 3.0);
+        assert_eq!(percentile(&sorted, 90.0), 3.0);
+        assert_eq!(percentile(&sorted, 100.0), 3.0);
+    }
+
+    #[test]
+    fn rows_are_aggregated_from_unsorted_values() {
+        let row = ProfileRow::new(String::from("blur"), vec![3.0, 1.0]);
+        assert_eq!(row.count, 2);
+        assert_eq!(row.total_millis, 4.0);
+        assert_eq!(row.mean_millis, 2.0);
+        assert_eq!(row.p50_millis, 1.0);
+        assert_eq!(row.p99_millis, 3.0);
+        assert_eq!(row.max_millis, 3.0);
+    }
+}
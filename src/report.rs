@@ -0,0 +1,240 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::batch::Task;
+use crate::commands::Description;
+use crate::error::Error;
+use crate::profile::Profile;
+
+/// The names of all supported report formats
+pub const REPORT_FORMATS: [&str; 1] = ["json"];
+
+/// Representation of the result of an image as an enum
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// The image was processed and stored to all outputs
+    Processed,
+    /// The image was skipped by an incremental build, as its outputs are up to date
+    UpToDate,
+    /// The image could not be processed
+    Failed,
+    /// The commands were only simulated on the dimensions of the image by a dry run
+    Planned,
+}
+
+/// Representation of the report of a single image as a struct
+#[derive(Serialize)]
+pub struct ImageReport {
+    /// Contains the position of the image in the batch, starting at 0
+    pub index: usize,
+    /// Contains the path of the input image
+    pub input: String,
+    /// Contains the result of the image
+    pub status: Status,
+    /// Contains the detected format of the input image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Contains the width of the input image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// Contains the height of the input image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Contains the wall time to load the input image in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_millis: Option<f64>,
+    /// Contains the applied commands in the order of execution
+    pub steps: Vec<StepReport>,
+    /// Contains the stored outputs of the image
+    pub outputs: Vec<OutputReport>,
+    /// Contains the error message, if the image could not be processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Contains the total wall time of the image in milliseconds
+    pub millis: f64,
+}
+
+/// Representation of the report of an applied command as a struct
+#[derive(Serialize)]
+pub struct StepReport {
+    /// Contains the name of the rendition, if the command is only applied to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendition: Option<String>,
+    /// Contains the structured description of the command
+    #[serde(flatten)]
+    pub description: Description,
+    /// Contains the width of the image after executing the command
+    pub width: u32,
+    /// Contains the height of the image after executing the command
+    pub height: u32,
+    /// Contains the wall time to execute the command in milliseconds
+    pub millis: f64,
+}
+
+/// Representation of the report of a stored output as a struct
+#[derive(Serialize)]
+pub struct OutputReport {
+    /// Contains the name of the rendition, if the output belongs to one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendition: Option<String>,
+    /// Contains the (resolved) output path
+    pub path: String,
+    /// Contains the format the image was stored in
+    pub format: String,
+    /// Contains the size of the stored file in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// Contains the width of the stored image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// Contains the height of the stored image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Contains the JPEG quality, which was chosen to fit into the maximum file size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    /// Contains the wall time of `apply_store` in milliseconds
+    pub store_millis: f64,
+    /// Contains the wall time to encode the image again with the encoder settings in milliseconds, if they apply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encode_millis: Option<f64>,
+    /// Contains the total wall time to store the image in milliseconds
+    pub millis: f64,
+}
+
+impl ImageReport {
+    /// This function returns a new, empty `ImageReport` of the supplied image
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The `Task` struct, containing the input path and the position of the image
+    /// * `status` - The result of the image
+    pub fn new(task: &Task, status: Status) -> Self {
+        ImageReport {
+            index: task.index,
+            input: task.input.display().to_string(),
+            status,
+            format: None,
+            width: None,
+            height: None,
+            load_millis: None,
+            steps: vec![],
+            outputs: vec![],
+            error: None,
+            millis: 0.0,
+        }
+    }
+
+    /// This function returns a new `ImageReport` of the supplied image, which could not be processed
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - The `Task` struct, containing the input path and the position of the image
+    /// * `error` - The `Error` which occurred
+    pub fn failed(task: &Task, error: &Error) -> Self {
+        ImageReport {
+            error: Some(error.to_string()),
+            ..ImageReport::new(task, Status::Failed)
+        }
+    }
+}
+
+/// Representation of the report of a run as a struct, which is shared by all worker threads
+pub struct Report {
+    /// Contains the reports of all images in the order they were finished
+    images: Mutex<Vec<ImageReport>>,
+    /// Contains whether the report is printed as JSON (`--report json`)
+    json: bool,
+    /// Contains whether the profile of the run is printed (`--profile`)
+    profile: bool,
+    /// Contains the instant the run started
+    start: Instant,
+}
+
+impl Report {
+    /// This function returns a new, empty `Report` of a run, which starts now
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - Whether the report is printed as JSON
+    /// * `profile` - Whether the profile of the run is printed, within the JSON report or as table
+    pub fn new(json: bool, profile: bool) -> Self {
+        Report {
+            images: Mutex::new(vec![]),
+            json,
+            profile,
+            start: Instant::now(),
+        }
+    }
+
+    /// This function checks, whether the report is printed as JSON, which replaces the progress messages
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Report` struct
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// This function adds the report of an image
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Report` struct
+    /// * `image` - The `ImageReport` of the image
+    pub fn add(&self, image: ImageReport) {
+        self.images.lock().unwrap().push(image);
+    }
+
+    /// This function prints the report as JSON, with the images in the order of the batch, and the profile of the run
+    ///
+    /// Without `--report json`, only the profile is printed as table.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `Report` struct
+    /// * `stderr` - Whether the report is printed to stderr, as stdout carries the image
+    pub fn print(self, stderr: bool) {
+        let mut images = self.images.into_inner().unwrap();
+        images.sort_by_key(|image| image.index);
+        let profile = match self.profile {
+            true => Some(Profile::new(&images, millis_since(self.start))),
+            false => None,
+        };
+
+        let report = match (self.json, profile) {
+            (true, profile) => {
+                serde_json::to_string_pretty(&RunReport { images, profile }).unwrap()
+            }
+            (false, Some(profile)) => profile.table(),
+            (false, None) => return,
+        };
+        if stderr {
+            eprintln!("{}", report);
+        } else {
+            println!("{}", report);
+        }
+    }
+}
+
+/// Representation of the printed report of a run as a struct
+#[derive(Serialize)]
+struct RunReport {
+    /// Contains the reports of all images in the order of the batch
+    images: Vec<ImageReport>,
+    /// Contains the profile of the run, if it is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<Profile>,
+}
+
+/// This function returns the elapsed wall time since the supplied instant in milliseconds, rounded to microseconds
+///
+/// # Arguments
+///
+/// * `start` - The instant the measured step started
+pub fn millis_since(start: Instant) -> f64 {
+    (start.elapsed().as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
@@ -0,0 +1,350 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::batch::{check_unique_renditions, Task};
+use crate::cli::{Commands, ARG_INCREMENTAL, ARG_WATCH, NAME_FILE_OUT};
+use crate::error::{Error, Result};
+use crate::manifest::Incremental;
+use crate::process::{plan_image, process_image, Progress};
+use crate::report::{ImageReport, Report, Status};
+use crate::stdio::is_stdio;
+use crate::template::{is_template, OutputTemplate};
+use crate::watch::watch;
+
+/// Name of the state file of the watch mode, which is placed in the watched directory by default
+const WATCH_STATE: &str = ".thumbnailer_watch.json";
+
+/// Representation of the options of a run, which apply to all images, as a struct
+pub struct Options {
+    /// Contains the path of the manifest of an incremental build (`--incremental`), if images should be skipped when they are up to date
+    pub manifest: Option<PathBuf>,
+    /// Contains the `Report` struct, if a report should be printed instead of the progress messages
+    pub report: Option<Report>,
+    /// Contains whether the commands are only simulated on the dimensions of the images (`--dry-run`)
+    pub dry_run: bool,
+    /// Contains the directory to save the image to after each command (`--save-steps`), if any
+    pub save_steps: Option<PathBuf>,
+    /// Contains the number of worker threads, which process the images of a batch concurrently
+    pub jobs: usize,
+    /// Contains the function, which prints the error of a single image of a batch or of the watch mode, following the supplied prefix
+    pub on_error: fn(&str, &Error),
+}
+
+impl Options {
+    /// This function returns the directory to save the steps of the supplied image to, if any
+    ///
+    /// The steps of the images of a batch are saved to subdirectories named by the position and the file name of each image.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `Options` struct
+    /// * `task` - The `Task` struct, containing the input path and the position of the image
+    /// * `batch` - Whether several images are processed
+    fn steps_dir(&self, task: &Task, batch: bool) -> Option<PathBuf> {
+        let dir = self.save_steps.as_ref()?;
+        if !batch {
+            return Some(dir.clone());
+        }
+
+        let stem = task.input.file_stem().unwrap_or_default().to_string_lossy();
+        Some(dir.join(format!("{:03}_{}", task.index, stem)))
+    }
+}
+
+/// This function applies all supplied commands to each image of the supplied tasks
+///
+/// The commands are shared by all worker threads, which process the images of a batch concurrently.
+/// Errors of single images of a batch are passed to `on_error` of the `Options` and the remaining images are processed anyway.
+/// The report is printed and the manifest of an incremental build is saved, after all images are processed.
+/// Renditions, which would be stored to the same path, are rejected before any image is processed.
+///
+/// Returns `()`, or the `Error` which occurred, such as an `Error::Batch` if some images of a batch could not be processed
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `tasks` - The `Task` structs, containing the input and output paths of the images
+/// * `options` - The `Options` struct of the run
+///
+/// # Examples
+/// ```no_run
+/// # use thumbnailer::Resize;
+/// # use thumbnailer_cli::batch::collect_tasks;
+/// # use thumbnailer_cli::{run, Options, PipelineBuilder};
+/// # fn main() -> thumbnailer_cli::Result<()> {
+/// // Processes the images of the directory photos into the directory thumbnails
+/// let cmd_list = PipelineBuilder::new().resize(Resize::Width(800)).build()?;
+/// let tasks = collect_tasks(&[String::from("photos"), String::from("thumbnails")], false)?;
+/// let options = Options {
+///     manifest: None,
+///     report: None,
+///     dry_run: false,
+///     save_steps: None,
+///     jobs: 4,
+///     on_error: |prefix, error| eprintln!("{}{}", prefix, error),
+/// };
+/// run(&cmd_list, &tasks, options)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn run(cmd_list: &Commands, tasks: &[Task], options: Options) -> Result<()> {
+    check_unique_renditions(tasks, &cmd_list.renditions)?;
+
+    // A dry run neither skips nor records images
+    let incremental = match options.manifest.as_ref().filter(|_| !options.dry_run) {
+        Some(manifest) => Some(Incremental::open(manifest, cmd_list)?),
+        None => None,
+    };
+    let is_stdio_task = |task: &Task| is_stdio(&task.input) || is_stdio(&task.output);
+    if incremental.is_some() && tasks.iter().any(is_stdio_task) {
+        return Err(Error::argument(
+            ARG_INCREMENTAL,
+            String::from("incremental builds require files, not stdin or stdout (-)"),
+        ));
+    }
+
+    let result = if tasks.len() == 1 {
+        run_single(&tasks[0], cmd_list, &options, incremental.as_ref())
+    } else {
+        run_batch(tasks, cmd_list, &options, incremental.as_ref())
+    };
+
+    if let Some(report) = options.report {
+        report.print(tasks.len() == 1 && is_stdio(&tasks[0].output));
+    }
+
+    // The manifest records the processed images of a batch, even if other images failed
+    if let Some(incremental) = &incremental {
+        incremental.save()?;
+    }
+    result
+}
+
+/// This function processes a single image
+///
+/// Returns `()`, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input and output path of the image
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `options` - The `Options` struct of the run
+/// * `incremental` - The `Incremental` struct, if the image should be skipped when it is up to date
+fn run_single(
+    task: &Task,
+    cmd_list: &Commands,
+    options: &Options,
+    incremental: Option<&Incremental>,
+) -> Result<()> {
+    if is_stdio(&task.output) && cmd_list.encoding.format.is_none() {
+        return Err(Error::argument(
+            NAME_FILE_OUT,
+            String::from("writing the image to stdout (-) requires --format"),
+        ));
+    }
+
+    // stdout carries only the image, so all progress messages are moved to stderr
+    let progress = Progress {
+        prefix: String::new(),
+        stderr: is_stdio(&task.output),
+        quiet: options.report.as_ref().is_some_and(Report::is_json),
+    };
+    let result = run_task(task, cmd_list, options, incremental, false, &progress);
+    add_report(options.report.as_ref(), task, result)
+}
+
+/// This function processes the images of a batch concurrently
+///
+/// Returns `()`, or an `Error::Batch` if some images could not be processed
+///
+/// # Arguments
+///
+/// * `tasks` - The `Task` structs, containing the input and output paths of the images
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `options` - The `Options` struct of the run
+/// * `incremental` - The `Incremental` struct, if images should be skipped when they are up to date
+fn run_batch(
+    tasks: &[Task],
+    cmd_list: &Commands,
+    options: &Options,
+    incremental: Option<&Incremental>,
+) -> Result<()> {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.max(1).min(tasks.len()) {
+            scope.spawn(|| {
+                while let Some(task) = tasks.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    let progress = Progress {
+                        prefix: format!("[{}] ", task.input.display()),
+                        stderr: false,
+                        quiet: options.report.as_ref().is_some_and(Report::is_json),
+                    };
+                    let result = run_task(task, cmd_list, options, incremental, true, &progress);
+                    if let Err(error) = add_report(options.report.as_ref(), task, result) {
+                        (options.on_error)(&progress.prefix, &error);
+                        failed.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            });
+        }
+    });
+
+    match failed.into_inner() {
+        0 => Ok(()),
+        failed => Err(Error::Batch {
+            failed,
+            total: tasks.len(),
+        }),
+    }
+}
+
+/// This function watches the input directory and processes new or modified images into the output directory
+///
+/// The state of the processed images is kept in the manifest of an incremental build, so images are not processed again after a restart.
+/// A single line is printed per processed image.
+///
+/// Returns an `Error`, if the paths are invalid or the input directory could not be read
+///
+/// # Arguments
+///
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `paths` - The supplied input directory, followed by the output directory or output template
+/// * `recursive` - Whether the subdirectories should be watched as well
+/// * `interval` - The time between two polls of the input directory
+/// * `options` - The `Options` struct of the run, whose manifest is the state file (`.thumbnailer_watch.json` within the input directory by default)
+pub fn run_watch(
+    cmd_list: &Commands,
+    paths: &[String],
+    recursive: bool,
+    interval: Duration,
+    options: &Options,
+) -> Result<()> {
+    let (input_dir, output) = match paths {
+        [input_dir, output] if Path::new(input_dir).is_dir() && !is_stdio(Path::new(output)) => {
+            (Path::new(input_dir), output)
+        }
+        _ => return Err(Error::argument(
+            ARG_WATCH,
+            String::from(
+                "watch mode requires an input directory and an output directory or output template",
+            ),
+        )),
+    };
+    if is_template(output) {
+        OutputTemplate::parse(output)?;
+    }
+
+    let manifest = match &options.manifest {
+        Some(manifest) => manifest.clone(),
+        None => input_dir.join(WATCH_STATE),
+    };
+    let incremental = Incremental::open(&manifest, cmd_list)?;
+    let progress = Progress::quiet();
+    // The messages of the watch mode are printed to stderr, so stdout stays free for reports
+    let messages = Progress {
+        prefix: String::new(),
+        stderr: true,
+        quiet: false,
+    };
+
+    messages.print(format!(
+        "Watching {} (press Ctrl+C to stop)",
+        input_dir.display()
+    ));
+    let process = |task: &Task| {
+        let input_hash = match incremental.check(task)? {
+            Some(input_hash) => input_hash,
+            None => return Ok(()),
+        };
+
+        let image = process_image(task, cmd_list, None, &progress)?;
+        let outputs: Vec<String> = image
+            .outputs
+            .iter()
+            .map(|output| output.path.clone())
+            .collect();
+        messages.print(format!(
+            "Processed {} → {} in {:.0} ms",
+            task.input.display(),
+            outputs.join(", "),
+            image.millis
+        ));
+        incremental.record(task, input_hash, outputs);
+        incremental.save()
+    };
+    watch(input_dir, output, recursive, interval, |task| {
+        if let Err(error) = process(task) {
+            (options.on_error)(&format!("[{}] ", task.input.display()), &error);
+        }
+    })
+}
+
+/// This function adds the result of a single image to the report, if it is supplied
+///
+/// Returns `()`, or the `Error` of the image
+///
+/// # Arguments
+///
+/// * `report` - The `Report` struct, if a report should be printed
+/// * `task` - The `Task` struct, containing the input path and the position of the image
+/// * `result` - The `ImageReport` of the image, or the `Error` which occurred
+fn add_report(report: Option<&Report>, task: &Task, result: Result<ImageReport>) -> Result<()> {
+    match (report, result) {
+        (Some(report), Ok(image)) => report.add(image),
+        (Some(report), Err(error)) => {
+            report.add(ImageReport::failed(task, &error));
+            return Err(error);
+        }
+        (None, result) => {
+            result?;
+        }
+    }
+    Ok(())
+}
+
+/// This function processes a single image, unless it is up to date, or only plans it in a dry run
+///
+/// Returns the `ImageReport` of the image, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `task` - The `Task` struct, containing the input and output path of the image
+/// * `cmd_list` - The `Commands` struct, containing the commands to apply
+/// * `options` - The `Options` struct of the run
+/// * `incremental` - The `Incremental` struct, if the image should be skipped when it is up to date
+/// * `batch` - Whether the image is part of a batch
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+fn run_task(
+    task: &Task,
+    cmd_list: &Commands,
+    options: &Options,
+    incremental: Option<&Incremental>,
+    batch: bool,
+    progress: &Progress,
+) -> Result<ImageReport> {
+    if options.dry_run {
+        return plan_image(task, cmd_list, progress);
+    }
+
+    let incremental = match incremental {
+        Some(incremental) => match incremental.check(task)? {
+            Some(input_hash) => Some((incremental, input_hash)),
+            None => {
+                progress.print(format!("Up to date: {}", task.input.display()));
+                return Ok(ImageReport::new(task, Status::UpToDate));
+            }
+        },
+        None => None,
+    };
+
+    let steps_dir = options.steps_dir(task, batch);
+    let image = process_image(task, cmd_list, steps_dir.as_deref(), progress)?;
+    if let Some((incremental, input_hash)) = incremental {
+        let outputs = image.outputs.iter().map(|output| output.path.clone());
+        incremental.record(task, input_hash, outputs.collect());
+    }
+    Ok(image)
+}
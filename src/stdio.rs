@@ -0,0 +1,163 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::cli::{NAME_FILE_IN, NAME_FILE_OUT};
+use crate::error::{Error, Result};
+
+/// The path, which stands for stdin as input path and for stdout as output path
+pub const STDIO: &str = "-";
+
+/// Representation of a temporary file as a struct, which is removed when it is dropped
+pub struct TempFile {
+    /// Contains the path of the temporary file
+    pub path: PathBuf,
+}
+
+impl TempFile {
+    /// This function returns a new path in the temporary directory, which is unique for this process
+    ///
+    /// The file itself is not created.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the file, which has to be unique within this process
+    /// * `extension` - The extension of the file, as `thumbnailer` detects the format by it
+    pub fn new(name: &str, extension: &str) -> Self {
+        TempFile {
+            path: env::temp_dir().join(format!(
+                "thumbnailer_{}_{}.{}",
+                process::id(),
+                name,
+                extension
+            )),
+        }
+    }
+}
+
+impl Drop for TempFile {
+    /// This method removes the temporary file, if it (still) exists
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// This function checks, whether the supplied path stands for stdin or stdout
+///
+/// # Arguments
+///
+/// * `path` - The supplied input or output path
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new(STDIO)
+}
+
+/// This function reads the image from stdin into a temporary file, so it can be loaded by `thumbnailer`
+///
+/// The format is detected by the magic bytes of the image, and the temporary file gets the matching extension.
+///
+/// Returns the `TempFile` containing the image, or an `Error` if stdin could not be read or the format is unknown
+pub fn read_stdin() -> Result<TempFile> {
+    let io_error = |source| Error::Io {
+        arg: String::from(NAME_FILE_IN),
+        path: String::from("stdin"),
+        source,
+    };
+
+    let mut bytes = vec![];
+    io::stdin()
+        .lock()
+        .read_to_end(&mut bytes)
+        .map_err(io_error)?;
+    write_temp_image("stdin", &bytes)
+}
+
+/// This function writes the supplied image into a temporary file with the extension of its format
+///
+/// The format is detected by the magic bytes of the image.
+///
+/// Returns the `TempFile` containing the image, or an `Error` if the format is unknown or the file could not be written
+///
+/// # Arguments
+///
+/// * `name` - The name of the temporary file, which has to be unique within this process
+/// * `bytes` - The content of the image
+fn write_temp_image(name: &str, bytes: &[u8]) -> Result<TempFile> {
+    let extension = image::guess_format(bytes)
+        .ok()
+        .and_then(|format| format.extensions_str().first())
+        .ok_or_else(|| Error::UnsupportedFormat {
+            arg: String::from(NAME_FILE_IN),
+            format: String::from("of stdin"),
+        })?;
+
+    let file = TempFile::new(name, extension);
+    fs::write(&file.path, bytes).map_err(|source| Error::Io {
+        arg: String::from(NAME_FILE_IN),
+        path: file.path.display().to_string(),
+        source,
+    })?;
+    Ok(file)
+}
+
+/// This function writes the stored image to stdout
+///
+/// Returns `()`, or an `Error::Io` if the image could not be read or written
+///
+/// # Arguments
+///
+/// * `path` - The path of the stored image
+pub fn write_stdout(path: &Path) -> Result<()> {
+    let bytes = fs::read(path).map_err(|source| Error::Io {
+        arg: String::from(NAME_FILE_OUT),
+        path: path.display().to_string(),
+        source,
+    })?;
+
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(&bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|source| Error::Io {
+            arg: String::from(NAME_FILE_OUT),
+            path: String::from("stdout"),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use super::*;
+
+    #[test]
+    fn images_of_stdin_get_the_extension_of_their_format() {
+        for (name, path, extension) in &[
+            ("png", "resources/tests/test_small.png", "png"),
+            ("jpeg", "resources/tests/test.jpg", "jpg"),
+        ] {
+            let bytes = fs::read(path).unwrap();
+            let file = write_temp_image(name, &bytes).unwrap();
+            assert_eq!(
+                file.path.extension().and_then(OsStr::to_str),
+                Some(*extension)
+            );
+            assert_eq!(fs::read(&file.path).unwrap(), bytes);
+
+            // The temporary file is removed, when it is dropped
+            let path = file.path.clone();
+            drop(file);
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn images_of_stdin_with_an_unknown_format_are_rejected() {
+        match write_temp_image("unknown", b"no image") {
+            Err(Error::UnsupportedFormat { arg, .. }) => assert_eq!(arg, NAME_FILE_IN),
+            _ => panic!("expected an error for an unknown format"),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use thumbnailer::target::TargetFormat;
+use thumbnailer::{GenericThumbnail, Target};
+
+use crate::batch::Task;
+use crate::cli::{ARG_MAX_BYTES, NAME_FILE_OUT};
+use crate::commands::exif::ARG_EXIF;
+use crate::error::{Error, Result};
+use crate::format::{format_name, output_format, Encoding};
+use crate::process::Progress;
+use crate::report::{millis_since, OutputReport};
+use crate::stdio::{is_stdio, write_stdout, TempFile};
+use crate::template::{content_hash, is_template, OutputTemplate, Stored};
+
+/// This function stores the image to the supplied output path, after resolving it if it is an output template
+///
+/// The format is chosen by the extension of the (resolved) output path, unless it is supplied.
+/// If encoder settings apply to the format, the image is stored losslessly by `thumbnailer` and encoded again with these settings.
+///
+/// Returns the `OutputReport` of the (resolved) output path, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `image` - The image with all commands applied
+/// * `task` - The `Task` struct, containing the input path and the position of the image
+/// * `output` - The output path or output template to store the image to
+/// * `encoding` - The output format and the encoder settings
+/// * `keeps_metadata` - Whether the metadata of the source file is kept by an exif-command
+/// * `progress` - The `Progress` struct to print the progress messages of the image
+pub(crate) fn store_image<T: GenericThumbnail>(
+    image: T,
+    task: &Task,
+    output: &Path,
+    encoding: &Encoding,
+    keeps_metadata: bool,
+    progress: &Progress,
+) -> Result<OutputReport> {
+    let start = Instant::now();
+    let template = match output.to_str().filter(|output| is_template(output)) {
+        Some(template) => Some(OutputTemplate::parse(template)?),
+        None => None,
+    };
+    let output = match &template {
+        Some(template) => template.resolve(&task.input, task.index, None),
+        None => output.to_path_buf(),
+    };
+
+    let format = output_format(&output, encoding.format.as_deref())?;
+    let format_name = format_name(&format);
+    check_metadata(keeps_metadata, encoding, &format, &output)?;
+    if encoding.max_bytes.is_some() && !matches!(format, TargetFormat::Jpeg) {
+        return Err(Error::argument(
+            ARG_MAX_BYTES,
+            format!(
+                "the maximum file size is only supported for JPEG output, not for {}",
+                output.display()
+            ),
+        ));
+    }
+
+    // The dimensions and the hash are only known after storing, so the image is stored to a temporary file first
+    // The same applies to stdout, which is written from the temporary file
+    let template = template.filter(OutputTemplate::needs_stored);
+    let temp = if template.is_some() || is_stdio(&output) {
+        let extension = output.extension().and_then(OsStr::to_str);
+        Some(TempFile::new(
+            &task.index.to_string(),
+            extension.unwrap_or("tmp"),
+        ))
+    } else {
+        None
+    };
+    let file_store = match &temp {
+        Some(temp) => temp.path.clone(),
+        None => {
+            create_parent_dir(&output)?;
+            output.clone()
+        }
+    };
+
+    // Encoder settings are not supported by `thumbnailer`, so the image is stored as PNG to be encoded again
+    let (target, encode_format) = if encoding.applies_to(&format) {
+        (
+            Target::new(TargetFormat::Png, file_store.clone()),
+            Some(format),
+        )
+    } else {
+        (Target::new(format, file_store.clone()), None)
+    };
+    let apply_store = Instant::now();
+    image.apply_store(&target).map_err(|_| Error::Store {
+        arg: String::from(NAME_FILE_OUT),
+        path: file_store.display().to_string(),
+    })?;
+    let store_millis = millis_since(apply_store);
+
+    let mut quality = None;
+    let mut encode_millis = None;
+    if let Some(format) = encode_format {
+        let encode = Instant::now();
+        let result = encode_stored(&file_store, &format, encoding);
+        encode_millis = Some(millis_since(encode));
+        match result {
+            Ok(Some(chosen)) => {
+                progress.print(format!(
+                    "Quality: {} to fit into {} bytes",
+                    chosen,
+                    encoding.max_bytes.unwrap()
+                ));
+                quality = Some(chosen);
+            }
+            Ok(None) => {}
+            Err(error) => {
+                // Do not leave the losslessly stored image behind under the name of the output
+                let _ = fs::remove_file(&file_store);
+                return Err(error);
+            }
+        }
+    }
+
+    let bytes = fs::metadata(&file_store)
+        .map(|metadata| metadata.len())
+        .ok();
+    let dimensions = image::image_dimensions(&file_store).ok();
+
+    let output = match template {
+        Some(template) => {
+            let stored = read_stored(&file_store)?;
+            let output = template.resolve(&task.input, task.index, Some(&stored));
+            create_parent_dir(&output)?;
+            move_file(&file_store, &output)?;
+            output
+        }
+        None if is_stdio(&output) => {
+            write_stdout(&file_store)?;
+            PathBuf::from("stdout")
+        }
+        None => output,
+    };
+    progress.print(format!("Output file: {}", output.display()));
+
+    Ok(OutputReport {
+        rendition: None,
+        path: output.display().to_string(),
+        format: String::from(format_name),
+        bytes,
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        quality,
+        store_millis,
+        encode_millis,
+        millis: millis_since(start),
+    })
+}
+
+/// This function checks, whether the metadata kept by an exif-command survives storing the image with the supplied encoder settings
+///
+/// Encoder settings are applied by decoding and encoding the stored image again, which removes the metadata.
+///
+/// Returns `()`, or an `Error::Argument` if the metadata would be lost
+///
+/// # Arguments
+///
+/// * `keeps_metadata` - Whether the metadata of the source file is kept by an exif-command
+/// * `encoding` - The output format and the encoder settings
+/// * `format` - The `TargetFormat` the image is stored in
+/// * `output` - The (resolved) output path, to report errors
+pub(crate) fn check_metadata(
+    keeps_metadata: bool,
+    encoding: &Encoding,
+    format: &TargetFormat,
+    output: &Path,
+) -> Result<()> {
+    if keeps_metadata && encoding.applies_to(format) {
+        return Err(Error::argument(
+            ARG_EXIF,
+            format!(
+                "the metadata can not be kept for {}, as the encoder settings ({}) encode the image again",
+                output.display(),
+                encoding
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// This function encodes the stored image again with the supplied encoder settings and overwrites it
+///
+/// Returns the JPEG quality chosen to fit into the maximum file size, if it is supplied, or the `Error` which occurred
+///
+/// # Arguments
+///
+/// * `path` - The path of the stored image
+/// * `format` - The `TargetFormat` to encode the image in
+/// * `encoding` - The encoder settings
+fn encode_stored(path: &Path, format: &TargetFormat, encoding: &Encoding) -> Result<Option<u8>> {
+    let image = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|source| Error::Io {
+            arg: String::from(NAME_FILE_OUT),
+            path: path.display().to_string(),
+            source,
+        })?
+        .decode()
+        .map_err(|_| Error::Load {
+            arg: String::from(NAME_FILE_OUT),
+            path: path.display().to_string(),
+        })?;
+
+    let (bytes, quality) = match encoding.max_bytes {
+        Some(max_bytes) => {
+            let (bytes, quality) = encoding.encode_max_bytes(&image, max_bytes, path)?;
+            (bytes, Some(quality))
+        }
+        None => (encoding.encode(&image, format, path)?, None),
+    };
+    fs::write(path, bytes).map_err(|source| Error::Io {
+        arg: String::from(NAME_FILE_OUT),
+        path: path.display().to_string(),
+        source,
+    })?;
+
+    Ok(quality)
+}
+
+/// This function creates the parent directory of the supplied output path, if it does not exist yet
+///
+/// Returns `()`, or an `Error::Io` if the directory could not be created
+///
+/// # Arguments
+///
+/// * `output` - The path of the image to store
+pub(crate) fn create_parent_dir(output: &Path) -> Result<()> {
+    if let Some(parent) = output.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(parent).map_err(|source| Error::Io {
+            arg: String::from(NAME_FILE_OUT),
+            path: parent.display().to_string(),
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// This function reads the dimensions and the content hash of the stored image
+///
+/// Returns a new `Stored` struct, or an `Error` if the file could not be read
+///
+/// # Arguments
+///
+/// * `path` - The path of the stored image
+fn read_stored(path: &Path) -> Result<Stored> {
+    let bytes = fs::read(path).map_err(|source| Error::Io {
+        arg: String::from(NAME_FILE_OUT),
+        path: path.display().to_string(),
+        source,
+    })?;
+    let (width, height) = image::image_dimensions(path).map_err(|_| Error::Load {
+        arg: String::from(NAME_FILE_OUT),
+        path: path.display().to_string(),
+    })?;
+
+    Ok(Stored {
+        width,
+        height,
+        hash: content_hash(&bytes),
+    })
+}
+
+/// This function moves the supplied file, falling back to copying if it is located on another file system
+///
+/// Returns `()`, or an `Error::Io` if the file could not be moved
+///
+/// # Arguments
+///
+/// * `from` - The path of the file to move
+/// * `to` - The new path of the file
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    fs::rename(from, to)
+        .or_else(|_| fs::copy(from, to).and_then(|_| fs::remove_file(from)))
+        .map_err(|source| Error::Io {
+            arg: String::from(NAME_FILE_OUT),
+            path: to.display().to_string(),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use thumbnailer::Thumbnail;
+
+    use super::*;
+
+    #[test]
+    fn templates_ending_in_the_hash_are_stored() {
+        let dir = env::temp_dir().join(format!("thumbnailer_store_{}", std::process::id()));
+        let input = PathBuf::from("resources/tests/test_small.png");
+        let task = Task {
+            input: input.clone(),
+            output: dir.join("{hash}.png"),
+            index: 0,
+        };
+        let image = Thumbnail::load(input).unwrap();
+
+        let output = store_image(
+            image,
+            &task,
+            &task.output,
+            &Encoding::default(),
+            false,
+            &Progress::quiet(),
+        )
+        .unwrap();
+        let stored = PathBuf::from(&output.path);
+        let hash = stored.file_stem().and_then(OsStr::to_str).unwrap();
+        assert_eq!(output.format, "png");
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, &read_stored(&stored).unwrap().hash[..16]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn metadata_kept_by_exif_is_not_encoded_again() {
+        let quality = Encoding {
+            quality: Some(80),
+            ..Encoding::default()
+        };
+        let output = Path::new("out.jpg");
+        assert!(check_metadata(true, &quality, &TargetFormat::Jpeg, output).is_err());
+        assert!(check_metadata(true, &quality, &TargetFormat::Png, output).is_ok());
+        assert!(check_metadata(false, &quality, &TargetFormat::Jpeg, output).is_ok());
+        assert!(check_metadata(true, &Encoding::default(), &TargetFormat::Jpeg, output).is_ok());
+    }
+}
@@ -0,0 +1,284 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::cli::NAME_FILE_OUT;
+use crate::error::{Error, Result};
+
+/// Number of hex digits of the content hash inserted for `{hash}`
+const HASH_LENGTH: usize = 16;
+
+/// Representation of a placeholder of an output template as an enum
+#[derive(Clone, Copy, PartialEq)]
+enum Placeholder {
+    /// `{stem}`: the file name of the input without extension
+    Stem,
+    /// `{ext}`: the extension of the input
+    Ext,
+    /// `{dir}`: the parent directory of the input
+    Dir,
+    /// `{index}`: the position of the input in the batch, starting at 0
+    Index,
+    /// `{width}`: the width of the stored image
+    Width,
+    /// `{height}`: the height of the stored image
+    Height,
+    /// `{hash}`: the beginning of the SHA-256 hash of the stored image
+    Hash,
+}
+
+/// Representation of a part of an output template as an enum
+enum Part {
+    /// Text, which is copied as it is
+    Literal(String),
+    /// A placeholder, which is replaced by its value
+    Placeholder(Placeholder),
+}
+
+/// Representation of the stored image, which is known only after the pipeline has run, as a struct
+pub struct Stored {
+    /// Contains the width of the stored image
+    pub width: u32,
+    /// Contains the height of the stored image
+    pub height: u32,
+    /// Contains the hex-encoded SHA-256 hash of the stored file
+    pub hash: String,
+}
+
+/// Representation of an `OUTPUT_PATH` containing placeholders, such as `out/{stem}_{width}x{height}.{ext}`, as a struct
+pub struct OutputTemplate {
+    /// Contains the literal text and the placeholders of the template in the given order
+    parts: Vec<Part>,
+}
+
+/// This function checks, whether the supplied output path is a template
+///
+/// # Arguments
+///
+/// * `path` - The supplied output path
+pub fn is_template(path: &str) -> bool {
+    path.contains('{')
+}
+
+impl OutputTemplate {
+    /// This function parses the supplied output path into its literal text and placeholders
+    ///
+    /// Returns a new `OutputTemplate`, or an `Error` if a placeholder is unknown or not closed
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The supplied output path
+    ///
+    /// # Examples
+    /// ```
+    /// # use thumbnailer_cli::template::OutputTemplate;
+    /// let template = OutputTemplate::parse("out/{stem}_{width}x{height}.{ext}").unwrap();
+    /// assert!(template.needs_stored());
+    /// ```
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = vec![];
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(String::from(&rest[..start])));
+            }
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => {
+                    return Err(Error::argument(
+                        NAME_FILE_OUT,
+                        format!("the placeholder in {} is not closed by }}", template),
+                    ))
+                }
+            };
+            let placeholder = match &rest[start + 1..end] {
+                "stem" => Placeholder::Stem,
+                "ext" => Placeholder::Ext,
+                "dir" => Placeholder::Dir,
+                "index" => Placeholder::Index,
+                "width" => Placeholder::Width,
+                "height" => Placeholder::Height,
+                "hash" => Placeholder::Hash,
+                name => {
+                    return Err(Error::argument(
+                        NAME_FILE_OUT,
+                        format!(
+                            "unknown placeholder {{{}}}, expected one of {{stem}}, {{ext}}, {{dir}}, {{index}}, {{width}}, {{height}} or {{hash}}",
+                            name
+                        ),
+                    ))
+                }
+            };
+            parts.push(Part::Placeholder(placeholder));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(String::from(rest)));
+        }
+
+        Ok(OutputTemplate { parts })
+    }
+
+    /// This function checks, whether the template contains placeholders of the stored image (`{width}`, `{height}` or `{hash}`)
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OutputTemplate`
+    pub fn needs_stored(&self) -> bool {
+        self.parts.iter().any(|part| match part {
+            Part::Placeholder(placeholder) => {
+                [Placeholder::Width, Placeholder::Height, Placeholder::Hash].contains(placeholder)
+            }
+            Part::Literal(_) => false,
+        })
+    }
+
+    /// This function checks, whether the template contains the placeholder `{hash}` of the stored image
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OutputTemplate`
+    pub fn needs_hash(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Placeholder(Placeholder::Hash)))
+    }
+
+    /// This function replaces all placeholders by the values of the supplied image
+    ///
+    /// The placeholders of the stored image are kept as they are, as long as `stored` is `None`.
+    /// This allows to determine the extension (and thus the format) of the output before the image is stored,
+    /// even if a placeholder is directly followed by the extension, such as `out/{hash}.png`.
+    ///
+    /// Returns the resolved output path as `PathBuf`
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - The `OutputTemplate`
+    /// * `input` - The path of the input image
+    /// * `index` - The position of the input image in the batch
+    /// * `stored` - The `Stored` image, or `None` if it is not stored yet
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::path::{Path, PathBuf};
+    /// # use thumbnailer_cli::template::OutputTemplate;
+    /// let template = OutputTemplate::parse("{dir}/thumbs/{stem}@2x.{ext}").unwrap();
+    /// let path = template.resolve(Path::new("photos/cat.jpg"), 0, None);
+    /// assert_eq!(path, PathBuf::from("photos/thumbs/cat@2x.jpg"));
+    /// ```
+    pub fn resolve(&self, input: &Path, index: usize, stored: Option<&Stored>) -> PathBuf {
+        let to_string =
+            |value: Option<&OsStr>| value.map(|value| value.to_string_lossy().to_string());
+
+        let mut path = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(text) => text.clone(),
+                Part::Placeholder(Placeholder::Stem) => {
+                    to_string(input.file_stem()).unwrap_or_default()
+                }
+                Part::Placeholder(Placeholder::Ext) => {
+                    to_string(input.extension()).unwrap_or_default()
+                }
+                Part::Placeholder(Placeholder::Dir) => match input.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent.display().to_string(),
+                    _ => String::from("."),
+                },
+                Part::Placeholder(Placeholder::Index) => index.to_string(),
+                Part::Placeholder(Placeholder::Width) => match stored {
+                    Some(stored) => stored.width.to_string(),
+                    None => String::from("{width}"),
+                },
+                Part::Placeholder(Placeholder::Height) => match stored {
+                    Some(stored) => stored.height.to_string(),
+                    None => String::from("{height}"),
+                },
+                Part::Placeholder(Placeholder::Hash) => match stored {
+                    Some(stored) => stored.hash.chars().take(HASH_LENGTH).collect(),
+                    None => String::from("{hash}"),
+                },
+            };
+            path.push_str(&value);
+        }
+
+        PathBuf::from(path)
+    }
+}
+
+/// This function returns the hex-encoded SHA-256 hash of the supplied bytes
+///
+/// # Arguments
+///
+/// * `bytes` - The content to hash
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// This function returns the message of the error of parsing the supplied output template
+    fn error(template: &str) -> String {
+        match OutputTemplate::parse(template) {
+            Err(Error::Argument { message, .. }) => message,
+            _ => panic!("expected an error parsing {}", template),
+        }
+    }
+
+    #[test]
+    fn placeholders_of_the_input_are_resolved() {
+        let template = OutputTemplate::parse("{dir}/{index}_{stem}.{ext}").unwrap();
+        assert!(!template.needs_stored());
+        assert_eq!(
+            template.resolve(Path::new("photos/cat.jpg"), 4, None),
+            PathBuf::from("photos/4_cat.jpg")
+        );
+        assert_eq!(
+            template.resolve(Path::new("cat.jpg"), 0, None),
+            PathBuf::from("./0_cat.jpg")
+        );
+    }
+
+    #[test]
+    fn placeholders_of_the_stored_image_are_kept_until_it_is_stored() {
+        let template = OutputTemplate::parse("out/{stem}_{width}x{height}/{hash}.png").unwrap();
+        assert!(template.needs_stored());
+        assert!(template.needs_hash());
+        let input = Path::new("cat.jpg");
+        assert_eq!(
+            template.resolve(input, 0, None),
+            PathBuf::from("out/cat_{width}x{height}/{hash}.png")
+        );
+
+        let stored = Stored {
+            width: 320,
+            height: 240,
+            hash: String::from("0123456789abcdef0123456789abcdef"),
+        };
+        assert_eq!(
+            template.resolve(input, 0, Some(&stored)),
+            PathBuf::from("out/cat_320x240/0123456789abcdef.png")
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let message = error("out/{name}.png");
+        assert!(
+            message.starts_with("unknown placeholder {name}"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn unclosed_placeholders_are_rejected() {
+        assert_eq!(
+            error("out/{stem.png"),
+            "the placeholder in out/{stem.png is not closed by }"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::batch::{collect_directory, Task};
+use crate::error::Result;
+
+/// Representation of the size and modification time of a file as a struct
+///
+/// A file is considered complete, as soon as its snapshot did not change between two polls.
+#[derive(Clone, Copy, PartialEq)]
+struct Snapshot {
+    /// Contains the size of the file in bytes
+    len: u64,
+    /// Contains the modification time of the file, if the platform supports it
+    modified: Option<SystemTime>,
+}
+
+impl Snapshot {
+    /// This function reads the snapshot of the supplied file
+    ///
+    /// Returns the `Snapshot`, or `None` if the file was removed in the meantime
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file
+    fn of(path: &Path) -> Option<Self> {
+        fs::metadata(path).ok().map(|metadata| Snapshot {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// This function polls the supplied directory and processes new or modified images, once their size has stabilised
+///
+/// The function runs until the directory can no longer be read, so it is usually ended by Ctrl+C.
+/// Errors of single images are handled by `process` and the image is not retried until it is modified again.
+///
+/// Returns an `Error::Io`, if the directory could not be read
+///
+/// # Arguments
+///
+/// * `input_dir` - The watched input directory
+/// * `output` - The output directory or output template
+/// * `recursive` - Whether the subdirectories should be watched as well
+/// * `interval` - The time between two polls
+/// * `process` - The function, which processes a single image
+pub fn watch<F>(
+    input_dir: &Path,
+    output: &str,
+    recursive: bool,
+    interval: Duration,
+    mut process: F,
+) -> Result<()>
+where
+    F: FnMut(&Task),
+{
+    // Images, whose snapshot was seen once, but which may still be written
+    let mut pending: HashMap<PathBuf, Snapshot> = HashMap::new();
+    // Images, which were processed (or failed) with the recorded snapshot
+    let mut done: HashMap<PathBuf, Snapshot> = HashMap::new();
+
+    loop {
+        let tasks = collect_directory(input_dir, output, recursive)?;
+        pending.retain(|path, _| tasks.iter().any(|task| &task.input == path));
+        done.retain(|path, _| tasks.iter().any(|task| &task.input == path));
+
+        for task in &tasks {
+            let snapshot = match Snapshot::of(&task.input) {
+                Some(snapshot) => snapshot,
+                None => continue,
+            };
+            if done.get(&task.input) == Some(&snapshot) {
+                continue;
+            }
+            if pending.get(&task.input) != Some(&snapshot) {
+                pending.insert(task.input.clone(), snapshot);
+                continue;
+            }
+
+            pending.remove(&task.input);
+            process(task);
+            done.insert(task.input.clone(), snapshot);
+        }
+
+        thread::sleep(interval);
+    }
+}